@@ -0,0 +1,30 @@
+use std::env;
+
+use futures::StreamExt;
+use telegram_bot::*;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
+    let secret_token =
+        env::var("TELEGRAM_WEBHOOK_SECRET").expect("TELEGRAM_WEBHOOK_SECRET not set");
+    let api = Api::new(token);
+
+    // Receive updates POSTed by Telegram, usually through a reverse proxy
+    let mut server = WebhookServer::bind(([127, 0, 0, 1], 8080).into())?;
+    server.path("/telegram").secret_token(secret_token);
+
+    while let Some(update) = server.next().await {
+        let update = update?;
+        if let UpdateKind::Message(message) = update.kind {
+            if let MessageKind::Text { ref data, .. } = message.kind {
+                api.send(message.text_reply(format!(
+                    "Hi, {}! You just wrote '{}'",
+                    &message.from.first_name, data
+                )))
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,29 +1,201 @@
+use std::path::Path;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
 use std::time::Duration;
 
-use futures::{Future, FutureExt};
-use tokio::time::timeout;
+use bytes::BytesMut;
+use futures::{stream, Future, FutureExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
 use tracing_futures::Instrument;
 
-use telegram_bot_raw::{HttpRequest, Request, ResponseType};
+use telegram_bot_raw::{
+    telegram_api_url, Body, ChatId, File, HttpRequest, MultipartValue, Request, ResponseType,
+    Update,
+};
 
-use crate::connector::{default_connector, Connector};
-use crate::errors::{Error, ErrorKind};
+use crate::chat_param::{request_chat, request_chat_id, with_chat_id};
+use crate::connector::{default_connector, Connector, DownloadStream};
+use crate::errors::{DownloadError, Error, ErrorKind, SpawnError};
+use crate::migration::{ChatMigrations, MigrationHook};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
 use crate::stream::UpdatesStream;
 
+const TELEGRAM_DOWNLOAD_LIMIT_BYTES: u64 = 20 * 1024 * 1024;
+const LOCAL_FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+type SpawnErrorHook = Arc<dyn Fn(&SpawnError) + Send + Sync>;
+
 /// Main type for sending requests to the Telegram bot API.
 #[derive(Clone)]
 pub struct Api(Arc<ApiInner>);
 
 struct ApiInner {
     token: String,
+    api_url: String,
     connector: Box<dyn Connector>,
+    download_limit: u64,
+    local_mode: bool,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    migrations: Option<ChatMigrations>,
+    spawned: AtomicUsize,
+    spawned_finished: Notify,
+    spawn_error_hook: Option<SpawnErrorHook>,
     next_request_id: AtomicUsize,
 }
 
+/// Builder for [`Api`](struct.Api.html) instances with non-default settings.
+///
+/// # Example
+///
+/// ```rust
+/// use telegram_bot::Api;
+///
+/// # fn main() {
+/// # let telegram_token = "token";
+/// let api = Api::builder(telegram_token)
+///     .download_limit(5 * 1024 * 1024)
+///     .build();
+/// # }
+/// ```
+pub struct ApiBuilder {
+    token: String,
+    api_url: String,
+    connector: Option<Box<dyn Connector>>,
+    download_limit: Option<u64>,
+    local_mode: bool,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    follow_chat_migrations: bool,
+    migration_hook: Option<MigrationHook>,
+    spawn_error_hook: Option<SpawnErrorHook>,
+}
+
+impl ApiBuilder {
+    /// Send requests to the Bot API server at this URL, e.g. a local Bot API server.
+    ///
+    /// Defaults to the `TELEGRAM_API_URL` environment variable, or `https://api.telegram.org/`.
+    pub fn api_url<T: Into<String>>(mut self, url: T) -> Self {
+        let mut url = url.into();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        self.api_url = url;
+        self
+    }
+
+    /// Use a custom connector instead of the default one.
+    pub fn connector(mut self, connector: Box<dyn Connector>) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Set the maximum size of files downloaded with
+    /// [`Api::download_file`](struct.Api.html#method.download_file).
+    ///
+    /// Defaults to 20 MB, the largest file the Telegram Bot API serves to bots,
+    /// or no limit in [local mode](#method.local_mode).
+    pub fn download_limit(mut self, limit: u64) -> Self {
+        self.download_limit = Some(limit);
+        self
+    }
+
+    /// Talk to a local Bot API server running on the same machine,
+    /// see [`api_url`](#method.api_url).
+    ///
+    /// In local mode files are downloaded by reading the local paths returned by `GetFile`,
+    /// and files uploaded with `InputFileUpload::with_path` are sent as `file://` references
+    /// instead of uploading their contents.
+    pub fn local_mode(mut self, local_mode: bool) -> Self {
+        self.local_mode = local_mode;
+        self
+    }
+
+    /// Automatically retry requests failed because of flood control or network errors.
+    ///
+    /// Requests aren't retried by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Delay outgoing messages to stay within Telegram's rate limits.
+    ///
+    /// Requests aren't rate limited by default.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Follow group to supergroup migrations.
+    ///
+    /// Migrations are learned from `migrate_to_chat_id` errors and from service messages
+    /// received through [`Api::stream`](struct.Api.html#method.stream). Requests which fail
+    /// because their group was migrated are sent again to the supergroup, and later requests
+    /// to the group are sent to the supergroup right away.
+    ///
+    /// Migrations aren't followed by default.
+    pub fn follow_chat_migrations(mut self, follow: bool) -> Self {
+        self.follow_chat_migrations = follow;
+        self
+    }
+
+    /// Call `hook` with the old and new chat ids when a migration is learned, so that
+    /// stored chat ids can be updated. Enables
+    /// [`follow_chat_migrations`](#method.follow_chat_migrations).
+    pub fn on_chat_migrated<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(ChatId, ChatId) + Send + Sync + 'static,
+    {
+        self.follow_chat_migrations = true;
+        self.migration_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` when a request sent with [`Api::spawn`](struct.Api.html#method.spawn) fails.
+    ///
+    /// Failed spawned requests are logged with `tracing` either way.
+    pub fn on_spawn_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&SpawnError) + Send + Sync + 'static,
+    {
+        self.spawn_error_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Create the `Api` instance.
+    pub fn build(self) -> Api {
+        Api(Arc::new(ApiInner {
+            token: self.token,
+            api_url: self.api_url,
+            connector: self.connector.unwrap_or_else(default_connector),
+            download_limit: self.download_limit.unwrap_or(if self.local_mode {
+                u64::MAX
+            } else {
+                TELEGRAM_DOWNLOAD_LIMIT_BYTES
+            }),
+            local_mode: self.local_mode,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            migrations: if self.follow_chat_migrations {
+                Some(ChatMigrations::new(self.migration_hook))
+            } else {
+                None
+            },
+            spawned: AtomicUsize::new(0),
+            spawned_finished: Notify::new(),
+            spawn_error_hook: self.spawn_error_hook,
+            next_request_id: AtomicUsize::new(0),
+        }))
+    }
+}
+
 impl Api {
     /// Create a new `Api` instance.
     ///
@@ -40,16 +212,28 @@ impl Api {
     /// # }
     /// ```
     pub fn new<T: AsRef<str>>(token: T) -> Self {
-        Self::with_connector(token, default_connector())
+        Self::builder(token).build()
     }
 
     /// Create a new `Api` instance wtih custom connector.
     pub fn with_connector<T: AsRef<str>>(token: T, connector: Box<dyn Connector>) -> Self {
-        Api(Arc::new(ApiInner {
+        Self::builder(token).connector(connector).build()
+    }
+
+    /// Create a builder for an `Api` instance with non-default settings.
+    pub fn builder<T: AsRef<str>>(token: T) -> ApiBuilder {
+        ApiBuilder {
             token: token.as_ref().to_string(),
-            connector,
-            next_request_id: AtomicUsize::new(0),
-        }))
+            api_url: telegram_api_url(),
+            connector: None,
+            download_limit: None,
+            local_mode: false,
+            retry_policy: None,
+            rate_limiter: None,
+            follow_chat_migrations: false,
+            migration_hook: None,
+            spawn_error_hook: None,
+        }
     }
 
     /// Create a stream which produces updates from the Telegram server.
@@ -73,7 +257,34 @@ impl Api {
         UpdatesStream::new(&self)
     }
 
-    /// Send a request to the Telegram server and do not wait for a response.
+    /// Returns the supergroup a group was migrated to, if the migration is known.
+    ///
+    /// Always returns `None` unless
+    /// [`ApiBuilder::follow_chat_migrations`](struct.ApiBuilder.html#method.follow_chat_migrations)
+    /// is enabled.
+    pub fn migrated_chat_id(&self, chat: ChatId) -> Option<ChatId> {
+        self.0
+            .migrations
+            .as_ref()
+            .and_then(|migrations| migrations.get(chat))
+    }
+
+    /// Learn group to supergroup migrations from service messages in `update`.
+    ///
+    /// Updates received through [`Api::stream`](#method.stream) are handled automatically,
+    /// call this for updates received in other ways, e.g. with a webhook.
+    pub fn learn_migration(&self, update: &Update) {
+        if let Some(ref migrations) = self.0.migrations {
+            migrations.learn(update);
+        }
+    }
+
+    /// Send a request to the Telegram server in the background.
+    ///
+    /// The returned handle resolves to the result of the request, it can be dropped
+    /// if the result isn't needed. Failures are logged and reported to the hook set with
+    /// [`ApiBuilder::on_spawn_error`](struct.ApiBuilder.html#method.on_spawn_error),
+    /// together with the method name and the target chat.
     ///
     /// # Examples
     ///
@@ -91,12 +302,70 @@ impl Api {
     /// # }
     /// # }
     /// ```
-    pub fn spawn<Req: Request>(&self, request: Req) {
+    pub fn spawn<Req: Request>(
+        &self,
+        request: Req,
+    ) -> JoinHandle<Result<<Req::Response as ResponseType>::Type, Error>>
+    where
+        <Req::Response as ResponseType>::Type: Send + 'static,
+    {
         let api = self.clone();
-        if let Ok(request) = request.serialize() {
-            tokio::spawn(async move {
-                let _ = api.send_http_request::<Req::Response>(request).await;
-            });
+        let request = request.serialize();
+        self.0.spawned.fetch_add(1, Ordering::SeqCst);
+        let guard = SpawnGuard(self.clone());
+        tokio::spawn(async move {
+            let _guard = guard;
+            let (method, chat, result) = match request {
+                Ok(request) => {
+                    let method = request.name_str().to_string();
+                    let chat = request_chat(&request.body);
+                    let result = api.send_http_request::<Req::Response>(request).await;
+                    (Some(method), chat, result)
+                }
+                Err(error) => (None, None, Err(ErrorKind::from(error).into())),
+            };
+            match result {
+                Ok(response) => Ok(response),
+                Err(error) => Err(api.report_spawn_error(SpawnError::new(method, chat, error))),
+            }
+        })
+    }
+
+    fn report_spawn_error(&self, error: SpawnError) -> Error {
+        tracing::warn!(error = %error, "spawned request failed");
+        if let Some(ref hook) = self.0.spawn_error_hook {
+            hook(&error);
+        }
+        error.into_error()
+    }
+
+    /// Wait until all requests sent with [`spawn`](#method.spawn) are finished,
+    /// giving up after `deadline`. Resolves to `true` if all requests finished in time.
+    ///
+    /// Use this before exiting, so that outgoing messages aren't lost.
+    pub fn drain(&self, deadline: Duration) -> impl Future<Output = bool> + Send {
+        let api = self.clone();
+        async move {
+            let drained = async {
+                loop {
+                    let finished = api.0.spawned_finished.notified();
+                    futures::pin_mut!(finished);
+                    finished.as_mut().enable();
+                    if api.0.spawned.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                    finished.await;
+                }
+            };
+            let drained = timeout(deadline, drained).await.is_ok();
+            if !drained {
+                let pending = api.0.spawned.load(Ordering::SeqCst);
+                tracing::warn!(
+                    pending = pending,
+                    "spawned requests not finished before deadline"
+                );
+            }
+            drained
         }
     }
 
@@ -170,6 +439,109 @@ impl Api {
         }
     }
 
+    /// Download contents of a file obtained with the `GetFile` request.
+    /// Resolves to a stream of chunks, which fails once the download limit is exceeded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, Document, prelude::*};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn download(api: Api, document: Document) -> Result<(), telegram_bot::Error> {
+    /// let file = api.send(document.get_file()).await?;
+    /// let mut stream = api.download_file(&file).await?;
+    /// while let Some(chunk) = stream.next().await {
+    ///     println!("received {} bytes", chunk?.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn download_file(
+        &self,
+        file: &File,
+    ) -> impl Future<Output = Result<DownloadStream, Error>> + Send {
+        let api = self.clone();
+        let file = file.clone();
+        async move {
+            let limit = api.0.download_limit;
+            if file.file_path.is_none() {
+                return Err(ErrorKind::from(DownloadError::MissingFilePath).into());
+            }
+            if let Some(size) = file.file_size {
+                if size as u64 > limit {
+                    let error = DownloadError::TooLarge {
+                        size: size as u64,
+                        limit,
+                    };
+                    return Err(ErrorKind::from(error).into());
+                }
+            }
+
+            tracing::trace!(file_id = %file.file_id, "downloading file");
+            let stream = if api.0.local_mode {
+                local_file_stream(file.file_path.as_ref().unwrap()).await?
+            } else {
+                api.0
+                    .connector
+                    .download(&api.0.api_url, &api.0.token, &file)
+                    .await?
+            };
+
+            let mut received = 0u64;
+            let stream = stream.map(move |chunk| {
+                let chunk = chunk?;
+                received += chunk.len() as u64;
+                if received > limit {
+                    let error = DownloadError::TooLarge {
+                        size: received,
+                        limit,
+                    };
+                    return Err(ErrorKind::from(error).into());
+                }
+                Ok(chunk)
+            });
+            Ok(Box::pin(stream) as DownloadStream)
+        }
+    }
+
+    /// Download contents of a file obtained with the `GetFile` request to `path`.
+    /// Resolves to the number of bytes written. The partially written file is removed
+    /// if the download fails.
+    pub fn download_file_to<P: AsRef<Path>>(
+        &self,
+        file: &File,
+        path: P,
+    ) -> impl Future<Output = Result<u64, Error>> + Send {
+        let download = self.download_file(file);
+        let path = path.as_ref().to_path_buf();
+        async move {
+            let mut stream = download.await?;
+            let mut output = tokio::fs::File::create(&path)
+                .await
+                .map_err(ErrorKind::from)?;
+
+            let result = async {
+                let mut written = 0u64;
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    output.write_all(&chunk).await.map_err(ErrorKind::from)?;
+                    written += chunk.len() as u64;
+                }
+                output.flush().await.map_err(ErrorKind::from)?;
+                Ok::<u64, Error>(written)
+            }
+            .await;
+
+            if result.is_err() {
+                drop(output);
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            result
+        }
+    }
+
     async fn send_http_request<Resp: ResponseType>(
         &self,
         request: HttpRequest,
@@ -177,21 +549,56 @@ impl Api {
         let request_id = self.0.next_request_id.fetch_add(1, Ordering::Relaxed);
         let span = tracing::trace_span!("send_http_request", request_id = request_id);
         async {
-            tracing::trace!(name = %request.name(), body = %request.body, "sending request");
-            let http_response = self.0.connector.request(&self.0.token, request).await?;
-            tracing::trace!(
-                response = %match http_response.body {
-                    Some(ref vec) => match std::str::from_utf8(vec) {
-                        Ok(str) => str,
-                        Err(_) => "<invalid utf-8 string>"
-                    },
-                    None => "<empty body>",
-                }, "response received"
-            );
-
-            let response = Resp::deserialize(http_response).map_err(ErrorKind::from)?;
-            tracing::trace!("response deserialized");
-            Ok(response)
+            let mut attempt = 1;
+            let mut waited = Duration::from_secs(0);
+            let mut request = if self.0.local_mode {
+                local_file_references(request)?
+            } else {
+                request
+            };
+            if let Some(ref migrations) = self.0.migrations {
+                request = migrations.rewrite(request);
+            }
+
+            let mut migrated = false;
+            loop {
+                let error = match self.send_http_request_once::<Resp>(request.clone()).await {
+                    Ok(response) => return Ok(response),
+                    Err(error) => error,
+                };
+
+                if let Some(ref migrations) = self.0.migrations {
+                    let from = request_chat_id(&request.body).map(ChatId::new);
+                    let to = error.migrate_to_chat_id().map(ChatId::new);
+                    if let (Some(from), Some(to)) = (from, to) {
+                        // Resend only once, so chats migrating back and forth can't loop forever.
+                        if from != to && !migrated {
+                            migrated = true;
+                            migrations.insert(from, to);
+                            tracing::debug!(name = %request.name_str(), "resending request to migrated chat");
+                            request = with_chat_id(request, to);
+                            continue;
+                        }
+                    }
+                }
+
+                let delay =
+                    self.0.retry_policy.as_ref().and_then(|policy| {
+                        policy.retry_delay(request.name_str(), &error, attempt, waited)
+                    });
+                let delay = match delay {
+                    Some(delay) => delay,
+                    None => return Err(error),
+                };
+
+                tracing::warn!(
+                    name = %request.name_str(), attempt = attempt, delay = ?delay, error = %error,
+                    "retrying request"
+                );
+                sleep(delay).await;
+                waited += delay;
+                attempt += 1;
+            }
         }
         .map(|result| {
             if let Err(ref error) = result {
@@ -202,4 +609,504 @@ impl Api {
         .instrument(span)
         .await
     }
+
+    async fn send_http_request_once<Resp: ResponseType>(
+        &self,
+        request: HttpRequest,
+    ) -> Result<Resp::Type, Error> {
+        if let Some(ref limiter) = self.0.rate_limiter {
+            limiter.acquire(&request).await;
+        }
+
+        tracing::trace!(name = %request.name_str(), body = %request.body, "sending request");
+        let http_response = self
+            .0
+            .connector
+            .request_with_api_url(&self.0.api_url, &self.0.token, request)
+            .await?;
+        tracing::trace!(
+            response = %match http_response.body {
+                Some(ref vec) => match std::str::from_utf8(vec) {
+                    Ok(str) => str,
+                    Err(_) => "<invalid utf-8 string>"
+                },
+                None => "<empty body>",
+            }, "response received"
+        );
+
+        let response = Resp::deserialize(http_response).map_err(ErrorKind::from)?;
+        tracing::trace!("response deserialized");
+        Ok(response)
+    }
+}
+
+/// Stream a file from the local filesystem, used in local mode.
+async fn local_file_stream(path: &str) -> Result<DownloadStream, Error> {
+    let file = tokio::fs::File::open(path).await.map_err(ErrorKind::from)?;
+    let stream = stream::try_unfold(file, |mut file| async move {
+        let mut chunk = BytesMut::with_capacity(LOCAL_FILE_CHUNK_SIZE);
+        match file.read_buf(&mut chunk).await.map_err(ErrorKind::from)? {
+            0 => Ok(None),
+            _ => Ok(Some((chunk.freeze(), file))),
+        }
+    });
+    Ok(Box::pin(stream))
+}
+
+/// Replace files uploaded from disk with `file://` references, used in local mode.
+fn local_file_references(mut request: HttpRequest) -> Result<HttpRequest, Error> {
+    if let Body::Multipart(ref mut parts) = request.body {
+        for (_, value) in parts.iter_mut() {
+            if let MultipartValue::Path { path, .. } = value {
+                let path = std::env::current_dir()
+                    .map_err(ErrorKind::from)?
+                    .join(AsRef::<Path>::as_ref(path));
+                let reference = format!("file://{}", path.display());
+                *value = MultipartValue::Text(reference.into());
+            }
+        }
+    }
+    Ok(request)
+}
+
+/// Counts a spawned request as finished when dropped, even if its task panicked or was aborted.
+struct SpawnGuard(Api);
+
+impl Drop for SpawnGuard {
+    fn drop(&mut self) {
+        if (self.0).0.spawned.fetch_sub(1, Ordering::SeqCst) == 1 {
+            (self.0).0.spawned_finished.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use futures::{stream, Future, StreamExt};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Client, Request, Response, Server};
+    use serde_json::json;
+    use telegram_bot_raw::{
+        telegram_api_url, AnswerInlineQuery, File, GetMe, HttpRequest, HttpResponse,
+        InlineQueryResultCachedPhoto, InputFileRef, InputFileUpload, InputTextMessageContent,
+        LogOut, MessageEntity, MessageEntityKind, RawRequest, SendMessage, SendPhoto, SetWebhook,
+        UserId,
+    };
+
+    use super::Api;
+    use crate::connector::hyper::HyperConnector;
+    use crate::connector::{Connector, DownloadStream, MockConnector};
+    use crate::errors::{DownloadError, Error, ErrorClass, ErrorKind};
+
+    #[derive(Debug)]
+    struct ChunksConnector(Vec<&'static [u8]>);
+
+    impl Connector for ChunksConnector {
+        fn request(
+            &self,
+            _token: &str,
+            req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            let error = ErrorKind::UnexpectedRequest(req.name_str().to_string());
+            Box::pin(async move { Err(error.into()) })
+        }
+
+        fn download(
+            &self,
+            _api_url: &str,
+            _token: &str,
+            _file: &File,
+        ) -> Pin<Box<dyn Future<Output = Result<DownloadStream, Error>> + Send>> {
+            let chunks = self.0.iter().map(|chunk| Ok(Bytes::from_static(chunk)));
+            let stream: DownloadStream = Box::pin(stream::iter(chunks.collect::<Vec<_>>()));
+            Box::pin(async move { Ok(stream) })
+        }
+    }
+
+    fn file(file_size: Option<i64>, file_path: Option<&str>) -> File {
+        File {
+            file_id: "file_id".into(),
+            file_size,
+            file_path: file_path.map(Into::into),
+        }
+    }
+
+    fn api(chunks: Vec<&'static [u8]>) -> Api {
+        Api::builder("token")
+            .connector(Box::new(ChunksConnector(chunks)))
+            .download_limit(8)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_download_file() {
+        let api = api(vec![b"hello ", b"!"]);
+        let stream = api.download_file(&file(Some(7), Some("a"))).await.unwrap();
+        let chunks: Vec<_> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(chunks.concat(), b"hello !");
+    }
+
+    #[tokio::test]
+    async fn test_download_file_errors() {
+        let api = api(vec![b"hello ", b"world"]);
+
+        let error = api.download_file(&file(None, None)).await.err().unwrap();
+        assert_eq!(
+            error.download_error(),
+            Some(&DownloadError::MissingFilePath)
+        );
+
+        let error = api
+            .download_file(&file(Some(9), Some("a")))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.download_error(),
+            Some(&DownloadError::TooLarge { size: 9, limit: 8 })
+        );
+
+        let mut stream = api.download_file(&file(None, Some("a"))).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            error.download_error(),
+            Some(&DownloadError::TooLarge { size: 11, limit: 8 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_file_to() {
+        let path = std::env::temp_dir().join(format!("telegram-bot-{}", std::process::id()));
+
+        let written = api(vec![b"hello"])
+            .download_file_to(&file(None, Some("a")), &path)
+            .await
+            .unwrap();
+        assert_eq!(written, 5);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"hello");
+
+        let result = api(vec![b"hello ", b"world"])
+            .download_file_to(&file(None, Some("a")), &path)
+            .await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    /// Fails every request, recording the URL it would be sent to.
+    #[derive(Debug, Default)]
+    struct UrlConnector(Arc<Mutex<Vec<String>>>);
+
+    impl Connector for UrlConnector {
+        fn request(
+            &self,
+            token: &str,
+            req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            self.request_with_api_url(&telegram_api_url(), token, req)
+        }
+
+        fn request_with_api_url(
+            &self,
+            api_url: &str,
+            token: &str,
+            req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            let url = req.url.url_with_base(api_url, token);
+            self.0.lock().unwrap().push(url);
+            Box::pin(async { Err(ErrorKind::DownloadUnsupported.into()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_url() {
+        let urls = Arc::new(Mutex::new(Vec::new()));
+        let local = Api::builder("local")
+            .api_url("http://localhost:8081")
+            .connector(Box::new(UrlConnector(urls.clone())))
+            .build();
+        let public = Api::builder("public")
+            .connector(Box::new(UrlConnector(urls.clone())))
+            .build();
+
+        assert!(local.send(GetMe).await.is_err());
+        assert!(public.send(GetMe).await.is_err());
+        assert_eq!(
+            *urls.lock().unwrap(),
+            vec![
+                "http://localhost:8081/botlocal/getMe".to_string(),
+                format!("{}botpublic/getMe", telegram_api_url()),
+            ]
+        );
+    }
+
+    /// Implements only the required method, as connectors written before custom server URLs.
+    #[derive(Debug)]
+    struct DefaultUrlConnector;
+
+    impl Connector for DefaultUrlConnector {
+        fn request(
+            &self,
+            _token: &str,
+            _req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            Box::pin(async {
+                Ok(HttpResponse {
+                    body: Some(br#"{"ok":true,"result":true}"#.to_vec()),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_url_connector() {
+        let api = Api::with_connector("token", Box::new(DefaultUrlConnector));
+        api.send(LogOut).await.unwrap();
+
+        let api = Api::builder("token")
+            .api_url("http://localhost:8081")
+            .connector(Box::new(DefaultUrlConnector))
+            .build();
+        let error = api.send(LogOut).await.unwrap_err();
+        assert!(error.to_string().contains("custom Bot API server"));
+    }
+
+    /// Stand-in for a local Bot API server, answers every request with `true`
+    /// and records the request bodies.
+    async fn local_server(bodies: Arc<Mutex<Vec<Bytes>>>) -> SocketAddr {
+        let make_service = make_service_fn(move |_| {
+            let bodies = bodies.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let bodies = bodies.clone();
+                    async move {
+                        let body = hyper::body::to_bytes(request.into_body()).await?;
+                        bodies.lock().unwrap().push(body);
+                        Ok::<_, hyper::Error>(Response::new(Body::from(
+                            r#"{"ok":true,"result":true}"#,
+                        )))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_local_mode() {
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let addr = local_server(bodies.clone()).await;
+        let api = Api::builder("token")
+            .api_url(format!("http://{}", addr))
+            .connector(Box::new(HyperConnector::new(Client::new())))
+            .local_mode(true)
+            .build();
+
+        let path = std::env::temp_dir().join("telegram-bot-local-mode-test");
+        tokio::fs::write(&path, b"local file").await.unwrap();
+        let file = file(Some(10), path.to_str());
+        let stream = api.download_file(&file).await.unwrap();
+        let chunks: Vec<_> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(chunks.concat(), b"local file");
+
+        let mut request = SetWebhook::new("https://example.com/telegram");
+        request.certificate(InputFileUpload::with_path("../data/sound.mp3"));
+        api.send(request).await.unwrap();
+        api.send(LogOut).await.unwrap();
+
+        let bodies = bodies.lock().unwrap();
+        let upload = String::from_utf8_lossy(&bodies[0]);
+        let reference = format!("file://{}", std::env::current_dir().unwrap().display());
+        assert!(upload.contains(&reference));
+        assert!(upload.contains("data/sound.mp3"));
+        assert!(bodies[0].len() < 1024);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_upload_stream() {
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let addr = local_server(bodies.clone()).await;
+        let api = Api::builder("token")
+            .api_url(format!("http://{}", addr))
+            .connector(Box::new(HyperConnector::new(Client::new())))
+            .build();
+
+        let chunks = stream::iter(vec![
+            Ok(Bytes::from_static(b"streamed ")),
+            Ok(Bytes::from_static(b"certificate")),
+        ]);
+        let mut request = SetWebhook::new("https://example.com/telegram");
+        request.certificate(InputFileUpload::with_stream(chunks, 20, "cert.pem"));
+        api.send(request.clone()).await.unwrap();
+        let error = api.send(request).await.unwrap_err();
+        assert_eq!(error.to_string(), "file stream was already sent");
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        let upload = String::from_utf8_lossy(&bodies[0]);
+        assert!(upload.contains("name=\"certificate\"; filename=\"cert.pem\""));
+        assert!(upload.contains("\r\n\r\nstreamed certificate\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_error() {
+        let mock = MockConnector::new();
+        mock.respond(
+            "sendMessage",
+            r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was blocked by the user"}"#,
+        );
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let hook_errors = errors.clone();
+        let api = Api::builder("token")
+            .connector(Box::new(mock))
+            .on_spawn_error(move |error| {
+                let chat = error.chat().map(ToString::to_string);
+                let method = error.method().map(str::to_string);
+                hook_errors
+                    .lock()
+                    .unwrap()
+                    .push((method, chat, error.error().class()))
+            })
+            .build();
+
+        let error = api
+            .spawn(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.class(), ErrorClass::BotBlocked);
+        assert_eq!(
+            *errors.lock().unwrap(),
+            vec![(
+                Some("sendMessage".to_string()),
+                Some("1".to_string()),
+                ErrorClass::BotBlocked
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_raw_request() {
+        let mock = MockConnector::new();
+        mock.respond(
+            "getMyCommands",
+            r#"{"ok":true,"result":[{"command":"start","description":"Start"}]}"#,
+        )
+        .respond(
+            "setChatTitle",
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: chat not found"}"#,
+        );
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+
+        let request = RawRequest::<Vec<serde_json::Value>>::json("getMyCommands", json!({}));
+        let commands = api.send(request).await.unwrap();
+        assert_eq!(commands[0]["command"], "start");
+
+        let request = RawRequest::<bool>::json("setChatTitle", json!({"chat_id": 1, "title": "t"}));
+        let error = api.send(request).await.unwrap_err();
+        assert_eq!(error.class(), ErrorClass::ChatNotFound);
+        assert_eq!(mock.request_bodies("setChatTitle")[0]["title"], "t");
+    }
+
+    #[tokio::test]
+    async fn test_entities() {
+        let message = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+        let mock = MockConnector::new();
+        mock.respond("sendMessage", message)
+            .respond("sendPhoto", message);
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+
+        let entities = vec![
+            MessageEntity {
+                offset: 0,
+                length: 2,
+                kind: MessageEntityKind::Bold,
+            },
+            MessageEntity {
+                offset: 3,
+                length: 4,
+                kind: MessageEntityKind::TextLink("https://t.me".into()),
+            },
+        ];
+        let expected = json!([
+            {"type": "bold", "offset": 0, "length": 2},
+            {"type": "text_link", "offset": 3, "length": 4, "url": "https://t.me"},
+        ]);
+
+        let mut request = SendMessage::new(UserId::new(1), "*1 <a>");
+        request.entities(entities.clone());
+        api.send(request).await.unwrap();
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap();
+        let bodies = mock.request_bodies("sendMessage");
+        assert_eq!(bodies[0]["entities"], expected);
+        assert!(bodies[1].get("entities").is_none());
+
+        let mut request = SendPhoto::new(UserId::new(1), InputFileRef::new("file_id"));
+        request.caption("*1 <a>").caption_entities(entities);
+        api.send(request).await.unwrap();
+        let caption_entities = &mock.request_bodies("sendPhoto")[0]["caption_entities"];
+        let caption_entities: serde_json::Value =
+            serde_json::from_str(caption_entities.as_str().unwrap()).unwrap();
+        assert_eq!(caption_entities, expected);
+    }
+
+    #[tokio::test]
+    async fn test_inline_caption_entities() {
+        let mock = MockConnector::new();
+        mock.respond("answerInlineQuery", r#"{"ok":true,"result":true}"#);
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+
+        let photo = InlineQueryResultCachedPhoto {
+            id: "1".into(),
+            photo_file_id: "file_id".into(),
+            title: None,
+            description: None,
+            caption: Some("*bold*".into()),
+            parse_mode: None,
+            caption_entities: vec![MessageEntity {
+                offset: 1,
+                length: 4,
+                kind: MessageEntityKind::Bold,
+            }],
+            reply_markup: None,
+            input_message_content: Some(
+                InputTextMessageContent::new("hi")
+                    .entities(vec![MessageEntity {
+                        offset: 0,
+                        length: 2,
+                        kind: MessageEntityKind::Italic,
+                    }])
+                    .into(),
+            ),
+        };
+        let query_id = serde_json::from_value(json!("query")).unwrap();
+        api.send(AnswerInlineQuery::new(query_id, vec![photo.into()]))
+            .await
+            .unwrap();
+
+        let body = &mock.request_bodies("answerInlineQuery")[0];
+        assert_eq!(
+            body["results"][0]["caption_entities"],
+            json!([{"type": "bold", "offset": 1, "length": 4}])
+        );
+        assert_eq!(
+            body["results"][0]["input_message_content"],
+            json!({
+                "message_text": "hi",
+                "entities": [{"type": "italic", "offset": 0, "length": 2}]
+            })
+        );
+    }
 }
@@ -0,0 +1,476 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use tokio::sync::Notify;
+
+use telegram_bot_raw::Update;
+
+use crate::shutdown::ShutdownHandle;
+use crate::stream::UpdatesStream;
+
+const BROADCAST_DEFAULT_CAPACITY: usize = 100;
+
+/// What happens when a subscriber's buffer is full, see
+/// [`UpdatesBroadcast::lag_policy`](struct.UpdatesBroadcast.html#method.lag_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Drop the oldest buffered update of the lagging subscriber,
+    /// other subscribers aren't affected.
+    DropOldest,
+    /// Wait until the lagging subscriber has room, which holds back all subscribers.
+    Block,
+}
+
+/// Hub which polls one [`UpdatesStream`](struct.UpdatesStream.html) and hands every update
+/// to all of its subscribers.
+///
+/// Each [`Subscription`](struct.Subscription.html) has its own bounded buffer, so subsystems
+/// of a bot consume updates independently without polling the token concurrently.
+/// Updates count as handled once the hub received them, so
+/// [`manual_ack`](struct.UpdatesStream.html#method.manual_ack) is turned off. Errors of
+/// the stream are logged, the stream retries on its own. Subscriptions end once the stream
+/// ends, e.g. after a [shutdown](#method.shutdown_handle), or when the hub is dropped.
+///
+/// Subscribers can be added while the hub is running through a
+/// [`SubscribeHandle`](struct.SubscribeHandle.html).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use telegram_bot::{Api, UpdatesBroadcast};
+/// use futures::StreamExt;
+///
+/// # async fn run(api: Api) {
+/// let mut hub = UpdatesBroadcast::new(api.stream());
+/// let mut commands = hub.subscribe();
+/// let mut analytics = hub.subscribe();
+/// tokio::spawn(hub.run());
+///
+/// tokio::spawn(async move {
+///     while let Some(update) = analytics.next().await {
+///         println!("{:?}", update);
+///     }
+/// });
+/// while let Some(update) = commands.next().await {
+///     // handle the update
+/// }
+/// # }
+/// ```
+pub struct UpdatesBroadcast {
+    stream: UpdatesStream,
+    capacity: usize,
+    lag_policy: LagPolicy,
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    channels: Vec<Arc<Channel>>,
+    closed: bool,
+}
+
+impl Subscribers {
+    fn subscribe(&mut self) -> Subscription {
+        let channel = Arc::new(Channel::default());
+        if self.closed {
+            channel.state.lock().unwrap().closed = true;
+        } else {
+            self.channels.push(channel.clone());
+        }
+        Subscription::new(channel)
+    }
+}
+
+impl UpdatesBroadcast {
+    /// Create a hub which polls `stream`.
+    pub fn new(mut stream: UpdatesStream) -> Self {
+        // Subscribers can't acknowledge updates, so the hub would stall after the first batch.
+        stream.manual_ack(false);
+        UpdatesBroadcast {
+            stream,
+            capacity: BROADCAST_DEFAULT_CAPACITY,
+            lag_policy: LagPolicy::DropOldest,
+            subscribers: Arc::new(Mutex::new(Subscribers::default())),
+        }
+    }
+
+    /// Set the number of updates buffered for each subscriber.
+    ///
+    /// Defaults to 100.
+    pub fn capacity(&mut self, capacity: usize) -> &mut Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set what happens when a subscriber's buffer is full.
+    ///
+    /// Defaults to [`LagPolicy::DropOldest`](enum.LagPolicy.html#variant.DropOldest).
+    pub fn lag_policy(&mut self, lag_policy: LagPolicy) -> &mut Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Create a new subscriber, which receives all updates received by the hub from now on.
+    pub fn subscribe(&mut self) -> Subscription {
+        self.subscribers.lock().unwrap().subscribe()
+    }
+
+    /// Create a handle for adding subscribers after the hub was moved into
+    /// [`run`](#method.run).
+    pub fn subscribe_handle(&self) -> SubscribeHandle {
+        SubscribeHandle {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+
+    /// Create a handle for stopping the underlying stream gracefully, see
+    /// [`UpdatesStream::shutdown_handle`](struct.UpdatesStream.html#method.shutdown_handle).
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.stream.shutdown_handle()
+    }
+
+    /// Poll the stream and distribute the updates until the stream ends.
+    pub async fn run(mut self) {
+        while let Some(update) = self.stream.next().await {
+            match update {
+                Ok(update) => self.publish(update).await,
+                Err(err) => tracing::error!(error = %err, "unable to receive updates"),
+            }
+        }
+
+        tracing::debug!("update stream ended, closing subscriptions");
+    }
+
+    async fn publish(&mut self, update: Update) {
+        let channels = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers
+                .channels
+                .retain(|channel| channel.state.lock().unwrap().subscribed);
+            subscribers.channels.clone()
+        };
+
+        for channel in &channels {
+            loop {
+                {
+                    let mut state = channel.state.lock().unwrap();
+                    if !state.subscribed {
+                        break;
+                    }
+                    if self.lag_policy == LagPolicy::DropOldest
+                        && state.buffer.len() >= self.capacity
+                    {
+                        state.buffer.pop_front();
+                        state.dropped += 1;
+                        tracing::warn!(update_id = update.id, "subscriber lagging, dropped update");
+                    }
+                    if state.buffer.len() < self.capacity {
+                        state.buffer.push_back(update.clone());
+                        channel.readable.notify_one();
+                        break;
+                    }
+                }
+                tracing::trace!(update_id = update.id, "waiting for lagging subscriber");
+                channel.writable.notified().await;
+            }
+        }
+    }
+}
+
+impl Drop for UpdatesBroadcast {
+    /// Close the subscriptions, also when [`run`](#method.run) is cancelled.
+    fn drop(&mut self) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        subscribers.closed = true;
+        for channel in subscribers.channels.drain(..) {
+            channel
+                .state
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .closed = true;
+            channel.readable.notify_one();
+        }
+    }
+}
+
+impl fmt::Debug for UpdatesBroadcast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdatesBroadcast")
+            .field("capacity", &self.capacity)
+            .field("lag_policy", &self.lag_policy)
+            .field(
+                "subscribers",
+                &self.subscribers.lock().unwrap().channels.len(),
+            )
+            .finish()
+    }
+}
+
+/// Handle for subscribing to a running [`UpdatesBroadcast`](struct.UpdatesBroadcast.html), see
+/// [`UpdatesBroadcast::subscribe_handle`](struct.UpdatesBroadcast.html#method.subscribe_handle).
+#[derive(Clone)]
+pub struct SubscribeHandle {
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+impl SubscribeHandle {
+    /// Create a new subscriber, which receives all updates received by the hub from now on.
+    ///
+    /// The subscription ends right away if the hub has already stopped.
+    pub fn subscribe(&self) -> Subscription {
+        self.subscribers.lock().unwrap().subscribe()
+    }
+}
+
+impl fmt::Debug for SubscribeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscribeHandle")
+            .field(
+                "subscribers",
+                &self.subscribers.lock().unwrap().channels.len(),
+            )
+            .finish()
+    }
+}
+
+/// Stream of the updates received by an [`UpdatesBroadcast`](struct.UpdatesBroadcast.html),
+/// see [`UpdatesBroadcast::subscribe`](struct.UpdatesBroadcast.html#method.subscribe).
+#[must_use = "streams do nothing unless polled"]
+pub struct Subscription {
+    channel: Arc<Channel>,
+    updates: Pin<Box<dyn Stream<Item = Update> + Send>>,
+}
+
+/// Buffer between the hub and one subscriber. Both sides have a single waiter,
+/// so `notify_one` never loses a wakeup.
+struct Channel {
+    state: Mutex<ChannelState>,
+    readable: Notify,
+    writable: Notify,
+}
+
+struct ChannelState {
+    buffer: VecDeque<Update>,
+    dropped: u64,
+    subscribed: bool,
+    closed: bool,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel {
+            state: Mutex::new(ChannelState {
+                buffer: VecDeque::new(),
+                dropped: 0,
+                subscribed: true,
+                closed: false,
+            }),
+            readable: Notify::new(),
+            writable: Notify::new(),
+        }
+    }
+}
+
+impl Subscription {
+    fn new(channel: Arc<Channel>) -> Self {
+        let updates = futures::stream::unfold(channel.clone(), |channel| async move {
+            loop {
+                {
+                    let mut state = channel.state.lock().unwrap();
+                    if let Some(update) = state.buffer.pop_front() {
+                        drop(state);
+                        channel.writable.notify_one();
+                        return Some((update, channel));
+                    }
+                    if state.closed {
+                        return None;
+                    }
+                }
+                channel.readable.notified().await;
+            }
+        });
+
+        Subscription {
+            channel,
+            updates: Box::pin(updates),
+        }
+    }
+
+    /// Number of updates dropped because this subscriber was lagging, see
+    /// [`LagPolicy::DropOldest`](enum.LagPolicy.html#variant.DropOldest).
+    pub fn dropped(&self) -> u64 {
+        self.channel.state.lock().unwrap().dropped
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Update;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.updates.as_mut().poll_next(cx)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.channel.state.lock().unwrap().subscribed = false;
+        self.channel.writable.notify_one();
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.channel.state.lock().unwrap();
+        f.debug_struct("Subscription")
+            .field("buffered", &state.buffer.len())
+            .field("dropped", &state.dropped)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use tokio::time::sleep;
+
+    use super::{LagPolicy, UpdatesBroadcast};
+    use crate::connector::MockConnector;
+    use crate::Api;
+
+    const UPDATES: &str = r#"{"ok":true,"result":[
+        {"update_id":10,"message":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}},
+        {"update_id":11,"message":{"message_id":2,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}},
+        {"update_id":12,"message":{"message_id":3,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}}
+    ]}"#;
+    const UPDATES_LATER: &str = r#"{"ok":true,"result":[
+        {"update_id":13,"message":{"message_id":4,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}}
+    ]}"#;
+    const NO_UPDATES: &str = r#"{"ok":true,"result":[]}"#;
+    const BAD_GATEWAY: &str = r#"{"ok":false,"error_code":502,"description":"Bad Gateway"}"#;
+
+    fn hub(lag_policy: LagPolicy) -> UpdatesBroadcast {
+        // Long polls after the first one fail, so that the stream backs off.
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", UPDATES)
+            .respond_if("getUpdates", |body| body["timeout"] == 0, NO_UPDATES)
+            .respond("getUpdates", BAD_GATEWAY);
+        let api = Api::with_connector("token", Box::new(mock));
+        let mut hub = UpdatesBroadcast::new(api.stream());
+        hub.capacity(2).lag_policy(lag_policy);
+        hub
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drop_oldest() {
+        let mut hub = hub(LagPolicy::DropOldest);
+        let mut subscription = hub.subscribe();
+        let shutdown = hub.shutdown_handle();
+        let run = tokio::spawn(hub.run());
+
+        sleep(Duration::from_secs(1)).await;
+        shutdown.shutdown();
+        run.await.unwrap();
+
+        let ids: Vec<_> = subscription
+            .by_ref()
+            .map(|update| update.id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![11, 12]);
+        assert_eq!(subscription.dropped(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_block() {
+        let mut hub = hub(LagPolicy::Block);
+        let mut first = hub.subscribe();
+        let mut second = hub.subscribe();
+        let dropped = hub.subscribe();
+        drop(dropped);
+        let shutdown = hub.shutdown_handle();
+        let run = tokio::spawn(hub.run());
+
+        assert_eq!(first.next().await.unwrap().id, 10);
+        let ids: Vec<_> = second
+            .by_ref()
+            .take(3)
+            .map(|update| update.id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![10, 11, 12]);
+        shutdown.shutdown();
+        run.await.unwrap();
+
+        let ids: Vec<_> = first.map(|update| update.id).collect().await;
+        assert_eq!(ids, vec![11, 12]);
+        assert_eq!(second.dropped(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_late_subscribe() {
+        let hub = hub(LagPolicy::DropOldest);
+        let handle = hub.subscribe_handle();
+        let shutdown = hub.shutdown_handle();
+        let run = tokio::spawn(hub.run());
+        let mut subscription = handle.subscribe();
+
+        sleep(Duration::from_secs(1)).await;
+        shutdown.shutdown();
+        run.await.unwrap();
+
+        let ids: Vec<_> = subscription
+            .by_ref()
+            .map(|update| update.id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![11, 12]);
+        assert!(handle.subscribe().next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancelled_run() {
+        let mut hub = hub(LagPolicy::Block);
+        let mut subscription = hub.subscribe();
+        let handle = hub.subscribe_handle();
+        let run = tokio::spawn(hub.run());
+
+        assert_eq!(subscription.next().await.unwrap().id, 10);
+        run.abort();
+        assert!(run.await.unwrap_err().is_cancelled());
+
+        let ids: Vec<_> = subscription.map(|update| update.id).collect().await;
+        assert!(ids.len() <= 2);
+        assert!(handle.subscribe().next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_manual_ack() {
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", UPDATES)
+            .respond_once("getUpdates", UPDATES_LATER)
+            .respond("getUpdates", BAD_GATEWAY);
+        let api = Api::with_connector("token", Box::new(mock));
+        let mut stream = api.stream();
+        stream.manual_ack(true);
+        let mut hub = UpdatesBroadcast::new(stream);
+        let mut subscription = hub.subscribe();
+        tokio::spawn(hub.run());
+
+        let ids: Vec<_> = subscription
+            .by_ref()
+            .take(4)
+            .map(|update| update.id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![10, 11, 12, 13]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Access to the `chat_id` parameter of serialized requests.
+
+use telegram_bot_raw::{Body, ChatId, ChatRef, HttpRequest, Integer, MultipartValue};
+
+/// `chat_id` parameter of the request.
+pub(crate) fn request_chat(body: &Body) -> Option<ChatRef> {
+    match body {
+        Body::Json(json) => {
+            let value: serde_json::Value = serde_json::from_str(json).ok()?;
+            match value.get("chat_id")? {
+                serde_json::Value::Number(id) => id.as_i64().map(|id| ChatRef::Id(id.into())),
+                serde_json::Value::String(chat) => Some(parse_chat(chat)),
+                _ => None,
+            }
+        }
+        Body::Multipart(parts) => parts.iter().find_map(|(name, value)| match value {
+            MultipartValue::Text(text) if *name == "chat_id" => Some(parse_chat(text.as_str())),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Numeric `chat_id` parameter of the request.
+pub(crate) fn request_chat_id(body: &Body) -> Option<Integer> {
+    match request_chat(body)? {
+        ChatRef::Id(id) => Some(id.into()),
+        _ => None,
+    }
+}
+
+fn parse_chat(chat: &str) -> ChatRef {
+    match chat.parse::<Integer>() {
+        Ok(id) => ChatRef::Id(id.into()),
+        Err(_) => ChatRef::ChannelUsername(chat.to_string()),
+    }
+}
+
+/// Replace the `chat_id` parameter of the request.
+pub(crate) fn with_chat_id(mut request: HttpRequest, chat: ChatId) -> HttpRequest {
+    let chat: Integer = chat.into();
+    match request.body {
+        Body::Json(ref mut json) => {
+            if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(json) {
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("chat_id".into(), chat.into());
+                    *json = value.to_string();
+                }
+            }
+        }
+        Body::Multipart(ref mut parts) => {
+            for (name, value) in parts.iter_mut() {
+                if *name == "chat_id" {
+                    *value = MultipartValue::Text(chat.to_string().into());
+                }
+            }
+        }
+        _ => (),
+    }
+    request
+}
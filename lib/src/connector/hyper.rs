@@ -1,37 +1,31 @@
-use std::io::{Cursor, Read};
 use std::path::Path;
 use std::pin::Pin;
 use std::str::FromStr;
 
-use bytes::Bytes;
-use futures::{Future, FutureExt};
+use futures::{Future, FutureExt, TryStreamExt};
 use hyper::{
     body::to_bytes,
     client::{connect::Connect, Client},
-    header::CONTENT_TYPE,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
     http::Error as HttpError,
-    Method, Request, Uri,
+    Method, Request, StatusCode, Uri,
 };
 #[cfg(feature = "rustls")]
 use hyper_rustls::HttpsConnector;
 #[cfg(feature = "openssl")]
 use hyper_tls::HttpsConnector;
-use multipart::client::lazy::Multipart;
 use telegram_bot_raw::{
-    Body as TelegramBody, HttpRequest, HttpResponse, Method as TelegramMethod, MultipartValue, Text,
+    telegram_api_url, Body as TelegramBody, File, HttpRequest, HttpResponse,
+    Method as TelegramMethod, MultipartValue,
 };
 
-use super::Connector;
-use crate::errors::{Error, ErrorKind};
+use super::multipart::MultipartBody;
+use super::{Connector, DownloadStream};
+use crate::errors::{DownloadError, Error, ErrorKind};
 
 #[derive(Debug)]
 pub struct HyperConnector<C>(Client<C>);
 
-enum MultipartTemporaryValue {
-    Text(Text),
-    Data { file_name: Text, data: Bytes },
-}
-
 impl<C> HyperConnector<C> {
     pub fn new(client: Client<C>) -> Self {
         HyperConnector(client)
@@ -44,7 +38,16 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
         token: &str,
         req: HttpRequest,
     ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
-        let uri = Uri::from_str(&req.url.url(token));
+        self.request_with_api_url(&telegram_api_url(), token, req)
+    }
+
+    fn request_with_api_url(
+        &self,
+        api_url: &str,
+        token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let uri = Uri::from_str(&req.url.url_with_base(api_url, token));
         let client = self.0.clone();
 
         let future = async move {
@@ -70,11 +73,11 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
                     http_request.body(Into::<hyper::Body>::into(body))
                 }
                 TelegramBody::Multipart(parts) => {
-                    let mut fields = Vec::new();
+                    let mut multipart = MultipartBody::new();
                     for (key, value) in parts {
                         match value {
                             MultipartValue::Text(text) => {
-                                fields.push((key, MultipartTemporaryValue::Text(text)))
+                                multipart.add_text(key, text.as_str());
                             }
                             MultipartValue::Path { file_name, path } => {
                                 let file_name = file_name
@@ -86,55 +89,34 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
                                     })
                                     .ok_or(ErrorKind::InvalidMultipartFilename)?;
 
-                                let data = tokio::fs::read(path).await.map_err(ErrorKind::from)?;
-                                fields.push((
-                                    key,
-                                    MultipartTemporaryValue::Data {
-                                        file_name,
-                                        data: data.into(),
-                                    },
-                                ))
+                                multipart
+                                    .add_file(key, file_name.as_str(), path)
+                                    .await
+                                    .map_err(ErrorKind::from)?;
                             }
-                            MultipartValue::Data { file_name, data } => fields
-                                .push((key, MultipartTemporaryValue::Data { file_name, data })),
-                        }
-                    }
-
-                    let mut prepared = {
-                        let mut part = Multipart::new();
-                        for (key, value) in &fields {
-                            match value {
-                                MultipartTemporaryValue::Text(text) => {
-                                    part.add_text(*key, text.as_str());
-                                }
-                                MultipartTemporaryValue::Data { file_name, data } => {
-                                    part.add_stream(
-                                        *key,
-                                        Cursor::new(data),
-                                        Some(file_name.as_str()),
-                                        None,
-                                    );
-                                }
+                            MultipartValue::Data { file_name, data } => {
+                                multipart.add_data(key, file_name.as_str(), data);
+                            }
+                            MultipartValue::Stream { file_name, stream } => {
+                                let length = stream.length();
+                                let stream = stream.take().ok_or(ErrorKind::FileStreamConsumed)?;
+                                multipart.add_stream(key, file_name.as_str(), stream, length);
                             }
                         }
-                        part.prepare().map_err(|err| err.error)
                     }
-                    .map_err(ErrorKind::from)?;
 
-                    let boundary = prepared.boundary();
-
-                    let content_type =
-                        format!("multipart/form-data;boundary={bound}", bound = boundary)
-                            .parse()
-                            .map_err(HttpError::from)
-                            .map_err(ErrorKind::from)?;
+                    let content_type = multipart
+                        .content_type()
+                        .parse()
+                        .map_err(HttpError::from)
+                        .map_err(ErrorKind::from)?;
+                    let content_length = multipart.content_length().into();
                     http_request.headers_mut().map(move |headers| {
                         headers.insert(CONTENT_TYPE, content_type);
+                        headers.insert(CONTENT_LENGTH, content_length);
                     });
 
-                    let mut bytes = Vec::new();
-                    prepared.read_to_end(&mut bytes).map_err(ErrorKind::from)?;
-                    http_request.body(bytes.into())
+                    http_request.body(hyper::Body::wrap_stream(multipart.into_stream()))
                 }
                 body => panic!("Unknown body type {:?}", body),
             }
@@ -155,6 +137,42 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
 
         future.boxed()
     }
+
+    fn download(
+        &self,
+        api_url: &str,
+        token: &str,
+        file: &File,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream, Error>> + Send>> {
+        let url = file.get_url_with_base(api_url, token);
+        let client = self.0.clone();
+
+        let future = async move {
+            let url = url
+                .ok_or(DownloadError::MissingFilePath)
+                .map_err(ErrorKind::from)?;
+            let uri = Uri::from_str(&url)
+                .map_err(HttpError::from)
+                .map_err(ErrorKind::from)?;
+
+            let response = client.get(uri).await.map_err(ErrorKind::from)?;
+            match response.status() {
+                status if status.is_success() => (),
+                StatusCode::NOT_FOUND => return Err(ErrorKind::from(DownloadError::Expired).into()),
+                status => {
+                    let error = DownloadError::UnexpectedStatus(status.as_u16());
+                    return Err(ErrorKind::from(error).into());
+                }
+            }
+
+            let body = response
+                .into_body()
+                .map_err(|error| Error::from(ErrorKind::from(error)));
+            Ok::<DownloadStream, Error>(Box::pin(body))
+        };
+
+        future.boxed()
+    }
 }
 
 pub fn default_connector() -> Result<Box<dyn Connector>, Error> {
@@ -0,0 +1,299 @@
+//! Connector which answers requests with canned responses, for testing bots offline.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::Future;
+use serde_json::{Map, Value};
+use telegram_bot_raw::{Body, HttpRequest, HttpResponse, MultipartValue};
+
+use super::Connector;
+use crate::errors::{Error, ErrorKind};
+
+/// A [`Connector`](../trait.Connector.html) which answers requests with canned JSON responses
+/// and records every request it receives.
+///
+/// Responses are matched by method name and, optionally, a predicate on the request body.
+/// The body is passed to the predicate as JSON, multipart bodies are converted to an object
+/// of their text fields. Responses are tried in the order they were added, requests without
+/// a matching response fail.
+///
+/// Clones share the responses and the recorded requests, so keep a clone around
+/// to make assertions after passing the connector to an `Api`.
+///
+/// # Example
+///
+/// ```rust
+/// use telegram_bot::connector::MockConnector;
+/// use telegram_bot::{Api, GetMe};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mock = MockConnector::new();
+/// mock.respond(
+///     "getMe",
+///     r#"{"ok":true,"result":{"id":1,"is_bot":true,"first_name":"bot"}}"#,
+/// );
+///
+/// let api = Api::with_connector("token", Box::new(mock.clone()));
+/// let me = api.send(GetMe).await.unwrap();
+/// assert_eq!(me.first_name, "bot");
+/// assert_eq!(mock.requests()[0].name_str(), "getMe");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct MockConnector {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: Vec<MockResponse>,
+    requests: Vec<HttpRequest>,
+}
+
+type BodyPredicate = Box<dyn Fn(&Value) -> bool + Send>;
+
+struct MockResponse {
+    method: String,
+    predicate: Option<BodyPredicate>,
+    response: String,
+    once: bool,
+}
+
+impl MockConnector {
+    /// Create a new `MockConnector` without any responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new `MockConnector` which replays a cassette written by
+    /// [`RecordingConnector`](../recording/struct.RecordingConnector.html).
+    ///
+    /// Every recorded response is used once, in the recorded order for each method.
+    pub fn from_cassette<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mock = Self::new();
+        let file = std::fs::File::open(path)?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: Value = serde_json::from_str(&line)?;
+            let method = entry
+                .get("method")
+                .and_then(Value::as_str)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing method"))?;
+            let response = entry.get("response").unwrap_or(&Value::Null);
+            mock.add(MockResponse {
+                method: method.to_string(),
+                predicate: None,
+                response: response.to_string(),
+                once: true,
+            });
+        }
+        Ok(mock)
+    }
+
+    /// Answer all requests to `method` with `response`.
+    pub fn respond<M, R>(&self, method: M, response: R) -> &Self
+    where
+        M: Into<String>,
+        R: Into<String>,
+    {
+        self.add(MockResponse {
+            method: method.into(),
+            predicate: None,
+            response: response.into(),
+            once: false,
+        })
+    }
+
+    /// Answer the next request to `method` with `response`.
+    pub fn respond_once<M, R>(&self, method: M, response: R) -> &Self
+    where
+        M: Into<String>,
+        R: Into<String>,
+    {
+        self.add(MockResponse {
+            method: method.into(),
+            predicate: None,
+            response: response.into(),
+            once: true,
+        })
+    }
+
+    /// Answer requests to `method` with `response` if `predicate` holds for the request body.
+    pub fn respond_if<M, F, R>(&self, method: M, predicate: F, response: R) -> &Self
+    where
+        M: Into<String>,
+        F: Fn(&Value) -> bool + Send + 'static,
+        R: Into<String>,
+    {
+        self.add(MockResponse {
+            method: method.into(),
+            predicate: Some(Box::new(predicate)),
+            response: response.into(),
+            once: false,
+        })
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Bodies of the requests to `method` received so far, as JSON.
+    pub fn request_bodies(&self, method: &str) -> Vec<Value> {
+        let state = self.state.lock().unwrap();
+        state
+            .requests
+            .iter()
+            .filter(|request| request.name_str() == method)
+            .map(|request| body_to_json(&request.body))
+            .collect()
+    }
+
+    fn add(&self, response: MockResponse) -> &Self {
+        self.state.lock().unwrap().responses.push(response);
+        self
+    }
+}
+
+impl Connector for MockConnector {
+    fn request(
+        &self,
+        _token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let body = body_to_json(&req.body);
+        let mut state = self.state.lock().unwrap();
+
+        let position = state.responses.iter().position(|response| {
+            response.method == req.name_str()
+                && match response.predicate {
+                    Some(ref predicate) => predicate(&body),
+                    None => true,
+                }
+        });
+        let result = match position {
+            Some(position) if state.responses[position].once => {
+                Ok(state.responses.remove(position).response)
+            }
+            Some(position) => Ok(state.responses[position].response.clone()),
+            None => {
+                Err(ErrorKind::UnexpectedRequest(format!("{} {}", req.name_str(), body)).into())
+            }
+        };
+        state.requests.push(req);
+
+        Box::pin(async move {
+            result.map(|response| HttpResponse {
+                body: Some(response.into_bytes()),
+            })
+        })
+    }
+
+    /// Answer the request, the server URL is ignored.
+    fn request_with_api_url(
+        &self,
+        _api_url: &str,
+        token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.request(token, req)
+    }
+}
+
+impl fmt::Debug for MockConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("MockConnector")
+            .field("responses", &state.responses.len())
+            .field("requests", &state.requests.len())
+            .finish()
+    }
+}
+
+/// Convert a request body to JSON. Multipart bodies are converted to an object
+/// of their text fields, files are represented by their file names.
+pub(crate) fn body_to_json(body: &Body) -> Value {
+    match body {
+        Body::Json(json) => serde_json::from_str(json).unwrap_or(Value::Null),
+        Body::Multipart(parts) => {
+            let fields = parts.iter().map(|(name, value)| {
+                let value = match value {
+                    MultipartValue::Text(text) => text.as_str().to_string(),
+                    MultipartValue::Path { path, file_name } => {
+                        file_name.as_ref().unwrap_or(path).as_str().to_string()
+                    }
+                    MultipartValue::Data { file_name, .. } => file_name.as_str().to_string(),
+                    MultipartValue::Stream { file_name, .. } => file_name.as_str().to_string(),
+                };
+                (name.to_string(), Value::String(value))
+            });
+            Value::Object(fields.collect::<Map<_, _>>())
+        }
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{GetMe, MessageKind, MessageOrChannelPost, SendMessage, UserId};
+
+    use super::MockConnector;
+    use crate::Api;
+
+    const GET_ME: &str = r#"{"ok":true,"result":{"id":1,"is_bot":true,"first_name":"bot"}}"#;
+
+    fn send_message(text: &str) -> String {
+        format!(
+            r#"{{"ok":true,"result":{{"message_id":1,"date":0,"chat":{{"id":1,"type":"private","first_name":"user"}},"from":{{"id":2,"is_bot":true,"first_name":"bot"}},"text":"{}"}}}}"#,
+            text
+        )
+    }
+
+    fn text(message: MessageOrChannelPost) -> String {
+        let kind = match message {
+            MessageOrChannelPost::Message(message) => message.kind,
+            MessageOrChannelPost::ChannelPost(post) => post.kind,
+        };
+        match kind {
+            MessageKind::Text { data, .. } => data,
+            kind => panic!("unexpected message kind: {:?}", kind),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_connector() {
+        let mock = MockConnector::new();
+        mock.respond("getMe", GET_ME)
+            .respond_if(
+                "sendMessage",
+                |body| body["text"] == "hello",
+                send_message("hello"),
+            )
+            .respond_once("sendMessage", send_message("first"));
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+
+        api.send(GetMe).await.unwrap();
+        api.send(GetMe).await.unwrap();
+        let chat = UserId::new(1);
+        let message = api.send(SendMessage::new(chat, "hello")).await.unwrap();
+        assert_eq!(text(message), "hello");
+        let message = api.send(SendMessage::new(chat, "other")).await.unwrap();
+        assert_eq!(text(message), "first");
+        assert!(api.send(SendMessage::new(chat, "other")).await.is_err());
+
+        assert_eq!(mock.requests().len(), 5);
+        let bodies = mock.request_bodies("sendMessage");
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[0]["chat_id"], 1);
+        assert_eq!(bodies[2]["text"], "other");
+    }
+}
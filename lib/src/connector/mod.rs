@@ -1,21 +1,64 @@
-//! Connector with hyper backend.
+//! Connector with hyper backend, and connectors for testing bots offline.
 
 pub mod hyper;
+pub mod mock;
+pub mod multipart;
+pub mod recording;
+
+pub use self::mock::MockConnector;
+pub use self::recording::RecordingConnector;
 
 use std::fmt::Debug;
 use std::pin::Pin;
 
-use futures::Future;
-use telegram_bot_raw::{HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::{Future, Stream};
+use telegram_bot_raw::{telegram_api_url, File, HttpRequest, HttpResponse};
+
+use crate::errors::{Error, ErrorKind};
 
-use crate::errors::Error;
+/// Stream of file contents produced by [`Connector::download`](trait.Connector.html#method.download).
+pub type DownloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>;
 
 pub trait Connector: Debug + Send + Sync {
+    /// Send a request to the default Bot API server.
     fn request(
         &self,
         token: &str,
         req: HttpRequest,
     ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>;
+
+    /// Send a request to the Bot API server at `api_url`, e.g. `https://api.telegram.org/`,
+    /// see [`ApiBuilder::api_url`](../struct.ApiBuilder.html#method.api_url).
+    ///
+    /// The default implementation delegates to [`request`](#tymethod.request) for the default
+    /// server and fails for other servers, connectors which support them should override it.
+    fn request_with_api_url(
+        &self,
+        api_url: &str,
+        token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        if api_url == telegram_api_url() {
+            self.request(token, req)
+        } else {
+            Box::pin(async { Err(ErrorKind::ApiUrlUnsupported.into()) })
+        }
+    }
+
+    /// Download contents of a file obtained with the `GetFile` request.
+    ///
+    /// The default implementation fails, connectors which are able to download
+    /// files should override it.
+    fn download(
+        &self,
+        api_url: &str,
+        token: &str,
+        file: &File,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream, Error>> + Send>> {
+        let _ = (api_url, token, file);
+        Box::pin(async { Err(ErrorKind::DownloadUnsupported.into()) })
+    }
 }
 
 pub fn default_connector() -> Box<dyn Connector> {
@@ -0,0 +1,353 @@
+//! Streaming `multipart/form-data` bodies.
+
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::{self, Stream, StreamExt};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use telegram_bot_raw::BoxFileStream;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const MULTIPART_BOUNDARY_LENGTH: usize = 32;
+const MULTIPART_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A `multipart/form-data` body which is streamed instead of being buffered in memory.
+///
+/// Text fields and in-memory data are kept as is, while files, readers and streams are only
+/// read when the body is sent. The total length is known up front, so the body can be sent
+/// with a `Content-Length` header.
+pub struct MultipartBody {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+struct Part {
+    header: Bytes,
+    body: PartBody,
+}
+
+enum PartBody {
+    Data(Bytes),
+    Reader {
+        reader: Pin<Box<dyn AsyncRead + Send>>,
+        length: u64,
+    },
+    Stream {
+        stream: BoxFileStream,
+        length: u64,
+    },
+}
+
+impl PartBody {
+    fn len(&self) -> u64 {
+        match self {
+            PartBody::Data(data) => data.len() as u64,
+            PartBody::Reader { length, .. } => *length,
+            PartBody::Stream { length, .. } => *length,
+        }
+    }
+}
+
+impl MultipartBody {
+    /// Create an empty body with a random boundary.
+    pub fn new() -> Self {
+        let boundary = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(MULTIPART_BOUNDARY_LENGTH)
+            .map(char::from)
+            .collect();
+
+        MultipartBody {
+            boundary,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Boundary used to separate the parts.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Value of the `Content-Type` header for this body.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Value of the `Content-Length` header for this body.
+    pub fn content_length(&self) -> u64 {
+        let parts: u64 = self
+            .parts
+            .iter()
+            .map(|part| part.header.len() as u64 + part.body.len() + 2)
+            .sum();
+        parts + self.closing_boundary().len() as u64
+    }
+
+    /// Add a text field.
+    pub fn add_text(&mut self, name: &str, text: &str) -> &mut Self {
+        let header = self.part_header(name, None);
+        self.parts.push(Part {
+            header,
+            body: PartBody::Data(Bytes::copy_from_slice(text.as_bytes())),
+        });
+        self
+    }
+
+    /// Add a file from memory.
+    pub fn add_data(&mut self, name: &str, file_name: &str, data: Bytes) -> &mut Self {
+        let header = self.part_header(name, Some(file_name));
+        self.parts.push(Part {
+            header,
+            body: PartBody::Data(data),
+        });
+        self
+    }
+
+    /// Add a file which is read from `reader` when the body is sent.
+    ///
+    /// `length` must be the exact number of bytes the reader produces,
+    /// sending the body fails if the reader ends early.
+    pub fn add_reader<R>(
+        &mut self,
+        name: &str,
+        file_name: &str,
+        reader: R,
+        length: u64,
+    ) -> &mut Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let header = self.part_header(name, Some(file_name));
+        self.parts.push(Part {
+            header,
+            body: PartBody::Reader {
+                reader: Box::pin(reader),
+                length,
+            },
+        });
+        self
+    }
+
+    /// Add a file which is read from `stream` when the body is sent.
+    ///
+    /// `length` must be the exact number of bytes the stream produces,
+    /// sending the body fails otherwise.
+    pub fn add_stream<S>(
+        &mut self,
+        name: &str,
+        file_name: &str,
+        stream: S,
+        length: u64,
+    ) -> &mut Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        let header = self.part_header(name, Some(file_name));
+        self.parts.push(Part {
+            header,
+            body: PartBody::Stream {
+                stream: Box::pin(stream),
+                length,
+            },
+        });
+        self
+    }
+
+    /// Add a file which is streamed from disk when the body is sent.
+    pub async fn add_file<P>(
+        &mut self,
+        name: &str,
+        file_name: &str,
+        path: P,
+    ) -> io::Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+        Ok(self.add_reader(name, file_name, file, length))
+    }
+
+    /// Convert the body into a stream of chunks.
+    pub fn into_stream(self) -> impl Stream<Item = io::Result<Bytes>> + Send {
+        let closing_boundary = Bytes::from(self.closing_boundary());
+        let parts = self.parts.into_iter().map(|Part { header, body }| {
+            let body = match body {
+                PartBody::Data(data) => stream::once(async move { Ok(data) }).boxed(),
+                PartBody::Reader { reader, length } => read_exact_stream(reader, length).boxed(),
+                PartBody::Stream { stream, length } => exact_stream(stream, length).boxed(),
+            };
+            stream::once(async move { Ok(header) })
+                .chain(body)
+                .chain(stream::once(async { Ok(Bytes::from_static(b"\r\n")) }))
+        });
+
+        stream::iter(parts)
+            .flatten()
+            .chain(stream::once(async move { Ok(closing_boundary) }))
+    }
+
+    fn part_header(&self, name: &str, file_name: Option<&str>) -> Bytes {
+        let mut header = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            self.boundary,
+            escape_quoted(name)
+        );
+        if let Some(file_name) = file_name {
+            header.push_str(&format!(
+                "; filename=\"{}\"\r\nContent-Type: application/octet-stream",
+                escape_quoted(file_name)
+            ));
+        }
+        header.push_str("\r\n\r\n");
+        header.into()
+    }
+
+    fn closing_boundary(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+}
+
+impl Default for MultipartBody {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn escape_quoted(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn read_exact_stream(
+    reader: Pin<Box<dyn AsyncRead + Send>>,
+    length: u64,
+) -> impl Stream<Item = io::Result<Bytes>> + Send {
+    stream::try_unfold(
+        (reader.take(length), length),
+        |(mut reader, remaining)| async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+
+            let capacity = remaining.min(MULTIPART_CHUNK_SIZE as u64) as usize;
+            let mut chunk = BytesMut::with_capacity(capacity);
+            let read = reader.read_buf(&mut chunk).await?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "multipart reader ended before its declared length",
+                ));
+            }
+
+            Ok(Some((chunk.freeze(), (reader, remaining - read as u64))))
+        },
+    )
+}
+
+fn exact_stream(
+    stream: BoxFileStream,
+    length: u64,
+) -> impl Stream<Item = io::Result<Bytes>> + Send {
+    stream::try_unfold((stream, length), |(mut stream, remaining)| async move {
+        match stream.next().await {
+            Some(chunk) => {
+                let chunk = chunk?;
+                if chunk.len() as u64 > remaining {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "multipart stream is longer than its declared length",
+                    ));
+                }
+                Ok(Some((
+                    chunk.clone(),
+                    (stream, remaining - chunk.len() as u64),
+                )))
+            }
+            None if remaining > 0 => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "multipart stream ended before its declared length",
+            )),
+            None => Ok(None),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::{stream, TryStreamExt};
+
+    use super::MultipartBody;
+
+    async fn collect(body: MultipartBody) -> std::io::Result<Vec<u8>> {
+        body.into_stream()
+            .try_fold(Vec::new(), |mut acc, chunk| async move {
+                acc.extend_from_slice(&chunk);
+                Ok(acc)
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_content_length() {
+        let mut body = MultipartBody::new();
+        body.add_text("chat_id", "42")
+            .add_data("photo", "image.jpg", Bytes::from_static(b"data"))
+            .add_reader("thumb", "thumb.jpg", &b"reader"[..], 6);
+        body.add_file("audio", "sound.mp3", "../data/sound.mp3")
+            .await
+            .unwrap();
+
+        let boundary = body.boundary().to_string();
+        let length = body.content_length();
+        let bytes = collect(body).await.unwrap();
+        assert_eq!(bytes.len() as u64, length);
+
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.starts_with(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n42\r\n",
+            boundary
+        )));
+        assert!(text.contains("name=\"photo\"; filename=\"image.jpg\""));
+        assert!(text.contains("\r\n\r\nreader\r\n"));
+        assert!(text.ends_with(&format!("--{}--\r\n", boundary)));
+    }
+
+    #[tokio::test]
+    async fn test_short_reader() {
+        let mut body = MultipartBody::new();
+        body.add_reader("document", "file.txt", &b"short"[..], 10);
+        assert!(collect(body).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let chunks = || {
+            stream::iter(vec![
+                Ok(Bytes::from_static(b"str")),
+                Ok(Bytes::from_static(b"eam")),
+            ])
+        };
+
+        let mut body = MultipartBody::new();
+        body.add_stream("document", "file.txt", chunks(), 6);
+        let length = body.content_length();
+        let bytes = collect(body).await.unwrap();
+        assert_eq!(bytes.len() as u64, length);
+        assert!(String::from_utf8_lossy(&bytes).contains("\r\n\r\nstream\r\n"));
+
+        let mut body = MultipartBody::new();
+        body.add_stream("document", "file.txt", chunks(), 10);
+        assert!(collect(body).await.is_err());
+
+        let mut body = MultipartBody::new();
+        body.add_stream("document", "file.txt", chunks(), 4);
+        assert!(collect(body).await.is_err());
+    }
+}
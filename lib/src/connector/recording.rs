@@ -0,0 +1,208 @@
+//! Connector which records requests and responses to a cassette file.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::{Future, FutureExt};
+use serde_json::{json, Value};
+use telegram_bot_raw::{telegram_api_url, File as TelegramFile, HttpRequest, HttpResponse};
+
+use super::mock::body_to_json;
+use super::{Connector, DownloadStream};
+use crate::errors::{Error, ErrorKind};
+
+/// A [`Connector`](../trait.Connector.html) which wraps another connector and writes
+/// every request and its response to a JSONL "cassette" file.
+///
+/// Each line holds the method name, the request body and the response as JSON.
+/// The bot token isn't recorded. File downloads are passed through without being recorded. The cassette can be replayed with
+/// [`MockConnector::from_cassette`](../mock/struct.MockConnector.html#method.from_cassette).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use telegram_bot::connector::{default_connector, RecordingConnector};
+/// use telegram_bot::Api;
+///
+/// # fn main() -> std::io::Result<()> {
+/// # let telegram_token = "token";
+/// let connector = RecordingConnector::new(default_connector(), "cassette.jsonl")?;
+/// let api = Api::with_connector(telegram_token, Box::new(connector));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingConnector {
+    inner: Box<dyn Connector>,
+    cassette: Arc<Mutex<File>>,
+}
+
+impl RecordingConnector {
+    /// Record requests sent through `inner` to the cassette at `path`,
+    /// an existing cassette is overwritten.
+    pub fn new<P: AsRef<Path>>(inner: Box<dyn Connector>, path: P) -> io::Result<Self> {
+        let cassette = File::create(path)?;
+        Ok(Self::with_file(inner, cassette))
+    }
+
+    /// Record requests sent through `inner`, appending to the cassette at `path`.
+    pub fn append<P: AsRef<Path>>(inner: Box<dyn Connector>, path: P) -> io::Result<Self> {
+        let cassette = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::with_file(inner, cassette))
+    }
+
+    fn with_file(inner: Box<dyn Connector>, cassette: File) -> Self {
+        RecordingConnector {
+            inner,
+            cassette: Arc::new(Mutex::new(cassette)),
+        }
+    }
+}
+
+impl Connector for RecordingConnector {
+    fn request(
+        &self,
+        token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.request_with_api_url(&telegram_api_url(), token, req)
+    }
+
+    fn request_with_api_url(
+        &self,
+        api_url: &str,
+        token: &str,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let method = req.name_str().to_string();
+        let body = body_to_json(&req.body);
+        let response = self.inner.request_with_api_url(api_url, token, req);
+        let cassette = self.cassette.clone();
+
+        let future = async move {
+            let response = response.await?;
+            let recorded = match response.body {
+                Some(ref body) => serde_json::from_slice(body)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into())),
+                None => Value::Null,
+            };
+            let entry = json!({
+                "method": method,
+                "body": body,
+                "response": recorded,
+            });
+
+            let mut cassette = cassette.lock().unwrap_or_else(|err| err.into_inner());
+            writeln!(cassette, "{}", entry).map_err(ErrorKind::from)?;
+            Ok(response)
+        };
+
+        future.boxed()
+    }
+
+    fn download(
+        &self,
+        api_url: &str,
+        token: &str,
+        file: &TelegramFile,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream, Error>> + Send>> {
+        self.inner.download(api_url, token, file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use bytes::Bytes;
+    use futures::{stream, Future, StreamExt};
+    use telegram_bot_raw::{File, GetMe, HttpRequest, HttpResponse, SendMessage, UserId};
+
+    use super::RecordingConnector;
+    use crate::connector::mock::MockConnector;
+    use crate::connector::{Connector, DownloadStream};
+    use crate::errors::{Error, ErrorKind};
+    use crate::Api;
+
+    const GET_ME: &str = r#"{"ok":true,"result":{"id":1,"is_bot":true,"first_name":"bot"}}"#;
+    const SEND_MESSAGE: &str = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = std::env::temp_dir().join("telegram-bot-cassette-test.jsonl");
+
+        let mock = MockConnector::new();
+        mock.respond("getMe", GET_ME)
+            .respond("sendMessage", SEND_MESSAGE);
+        let connector = RecordingConnector::new(Box::new(mock), &path).unwrap();
+        let api = Api::with_connector("secret-token", Box::new(connector));
+        api.send(GetMe).await.unwrap();
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap();
+
+        let cassette = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(cassette.lines().count(), 2);
+        assert!(cassette.contains(r#""method":"sendMessage""#));
+        assert!(!cassette.contains("secret-token"));
+
+        let replay = MockConnector::from_cassette(&path).unwrap();
+        let api = Api::with_connector("token", Box::new(replay.clone()));
+        let me = api.send(GetMe).await.unwrap();
+        assert_eq!(me.first_name, "bot");
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap();
+        assert!(api.send(GetMe).await.is_err());
+        assert_eq!(replay.request_bodies("sendMessage")[0]["text"], "hi");
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[derive(Debug)]
+    struct DownloadConnector;
+
+    impl Connector for DownloadConnector {
+        fn request(
+            &self,
+            _token: &str,
+            req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            let error = ErrorKind::UnexpectedRequest(req.name_str().to_string());
+            Box::pin(async move { Err(error.into()) })
+        }
+
+        fn download(
+            &self,
+            _api_url: &str,
+            _token: &str,
+            _file: &File,
+        ) -> Pin<Box<dyn Future<Output = Result<DownloadStream, Error>> + Send>> {
+            let stream: DownloadStream =
+                Box::pin(stream::iter(vec![Ok(Bytes::from_static(b"data"))]));
+            Box::pin(async move { Ok(stream) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download() {
+        let path = std::env::temp_dir().join("telegram-bot-cassette-download-test.jsonl");
+        let connector = RecordingConnector::new(Box::new(DownloadConnector), &path).unwrap();
+        let api = Api::with_connector("token", Box::new(connector));
+
+        let file = File {
+            file_id: "file_id".into(),
+            file_size: Some(4),
+            file_path: Some("a".into()),
+        };
+        let stream = api.download_file(&file).await.unwrap();
+        let chunks: Vec<_> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(chunks.concat(), b"data");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,18 +1,83 @@
 use std::error;
 use std::fmt;
 
-use telegram_bot_raw;
+use telegram_bot_raw::{self, ChatRef, Integer};
 
 #[derive(Debug)]
 pub struct Error(ErrorKind);
 
+/// Reasons why a file download failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DownloadError {
+    /// `File` has no `file_path`, request a fresh one with `GetFile`.
+    MissingFilePath,
+    /// The download link has expired, request a fresh one with `GetFile`.
+    Expired,
+    /// The file exceeds the configured download limit.
+    TooLarge {
+        /// Size of the file, or number of bytes received before the limit was hit.
+        size: u64,
+        /// Configured download limit.
+        limit: u64,
+    },
+    /// The server responded with an unexpected HTTP status code.
+    UnexpectedStatus(u16),
+}
+
+/// Failure of a request sent with [`Api::spawn`](struct.Api.html#method.spawn), see
+/// [`ApiBuilder::on_spawn_error`](struct.ApiBuilder.html#method.on_spawn_error).
+#[derive(Debug)]
+pub struct SpawnError {
+    method: Option<String>,
+    chat: Option<ChatRef>,
+    error: Error,
+}
+
+/// Classification of errors, see [`Error::class`](struct.Error.html#method.class).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorClass {
+    /// The user has blocked the bot.
+    BotBlocked,
+    /// The chat doesn't exist or the bot has no access to it.
+    ChatNotFound,
+    /// The new content and reply markup of an edited message are the same as the current ones.
+    MessageNotModified,
+    /// Flood control was exceeded, see [`Error::retry_after`](struct.Error.html#method.retry_after).
+    FloodControl,
+    /// The group has been migrated to a supergroup, see
+    /// [`Error::migrate_to_chat_id`](struct.Error.html#method.migrate_to_chat_id).
+    ChatMigrated,
+    /// Any other error returned by Telegram, see
+    /// [`Error::error_code`](struct.Error.html#method.error_code) and
+    /// [`Error::description`](struct.Error.html#method.description).
+    Telegram,
+    /// The request timed out.
+    Timeout,
+    /// The connection to the server failed.
+    Network,
+    /// The response could not be decoded.
+    Json,
+    /// A file download failed, see [`Error::download_error`](struct.Error.html#method.download_error).
+    Download,
+    /// Any other error.
+    Other,
+}
+
 #[derive(Debug)]
 pub(crate) enum ErrorKind {
     Raw(telegram_bot_raw::Error),
     Hyper(hyper::Error),
     Http(hyper::http::Error),
     Io(std::io::Error),
+    Json(serde_json::Error),
     InvalidMultipartFilename,
+    FileStreamConsumed,
+    Download(DownloadError),
+    DownloadUnsupported,
+    ApiUrlUnsupported,
+    UnexpectedRequest(String),
 }
 
 impl From<telegram_bot_raw::Error> for ErrorKind {
@@ -39,12 +104,175 @@ impl From<std::io::Error> for ErrorKind {
     }
 }
 
+impl From<serde_json::Error> for ErrorKind {
+    fn from(error: serde_json::Error) -> Self {
+        ErrorKind::Json(error)
+    }
+}
+
+impl From<DownloadError> for ErrorKind {
+    fn from(error: DownloadError) -> Self {
+        ErrorKind::Download(error)
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
         Error(kind)
     }
 }
 
+impl Error {
+    /// Classify the error.
+    pub fn class(&self) -> ErrorClass {
+        match &self.0 {
+            ErrorKind::Raw(error) => match error.description() {
+                Some(description) => {
+                    let description = description.to_lowercase();
+                    if description.contains("bot was blocked by the user") {
+                        ErrorClass::BotBlocked
+                    } else if description.contains("chat not found") {
+                        ErrorClass::ChatNotFound
+                    } else if description.contains("message is not modified") {
+                        ErrorClass::MessageNotModified
+                    } else if error.retry_after().is_some() {
+                        ErrorClass::FloodControl
+                    } else if error.migrate_to_chat_id().is_some() {
+                        ErrorClass::ChatMigrated
+                    } else {
+                        ErrorClass::Telegram
+                    }
+                }
+                None if error.is_json() => ErrorClass::Json,
+                None => ErrorClass::Other,
+            },
+            ErrorKind::Json(_) => ErrorClass::Json,
+            ErrorKind::Download(_) => ErrorClass::Download,
+            _ if self.is_timeout() => ErrorClass::Timeout,
+            _ if self.is_network() => ErrorClass::Network,
+            _ => ErrorClass::Other,
+        }
+    }
+
+    /// Error code returned by Telegram, e.g. `400` or `403`.
+    pub fn error_code(&self) -> Option<Integer> {
+        self.raw().and_then(telegram_bot_raw::Error::error_code)
+    }
+
+    /// Human-readable description of the error returned by Telegram.
+    pub fn description(&self) -> Option<&str> {
+        self.raw().and_then(telegram_bot_raw::Error::description)
+    }
+
+    /// In case of exceeding flood control, the number of seconds left to wait
+    /// before the request can be repeated.
+    pub fn retry_after(&self) -> Option<Integer> {
+        self.raw().and_then(telegram_bot_raw::Error::retry_after)
+    }
+
+    /// The group has been migrated to a supergroup with the specified identifier.
+    pub fn migrate_to_chat_id(&self) -> Option<Integer> {
+        self.raw()
+            .and_then(telegram_bot_raw::Error::migrate_to_chat_id)
+    }
+
+    /// Returns `true` if the connection to the server failed or was interrupted,
+    /// the request may or may not have been processed.
+    pub fn is_network(&self) -> bool {
+        match &self.0 {
+            ErrorKind::Hyper(error) => {
+                error.is_connect()
+                    || error.is_closed()
+                    || error.is_incomplete_message()
+                    || error.is_canceled()
+                    || error.is_timeout()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the request timed out.
+    pub fn is_timeout(&self) -> bool {
+        match &self.0 {
+            ErrorKind::Hyper(error) => error.is_timeout(),
+            ErrorKind::Io(error) => error.kind() == std::io::ErrorKind::TimedOut,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a response could not be decoded.
+    pub fn is_json(&self) -> bool {
+        match &self.0 {
+            ErrorKind::Raw(error) => error.is_json(),
+            ErrorKind::Json(_) => true,
+            _ => false,
+        }
+    }
+
+    fn raw(&self) -> Option<&telegram_bot_raw::Error> {
+        match &self.0 {
+            ErrorKind::Raw(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason of a failed file download.
+    pub fn download_error(&self) -> Option<&DownloadError> {
+        match &self.0 {
+            ErrorKind::Download(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl SpawnError {
+    pub(crate) fn new(method: Option<String>, chat: Option<ChatRef>, error: Error) -> Self {
+        SpawnError {
+            method,
+            chat,
+            error,
+        }
+    }
+
+    /// Name of the failed method, e.g. `"sendMessage"`.
+    /// `None` if the request could not be serialized.
+    pub fn method(&self) -> Option<&str> {
+        self.method.as_deref()
+    }
+
+    /// The chat the request was sent to, if it has a `chat_id` parameter.
+    pub fn chat(&self) -> Option<&ChatRef> {
+        self.chat.as_ref()
+    }
+
+    /// The error the request failed with.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Consume the `SpawnError`, returning the error the request failed with.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::MissingFilePath => write!(f, "file path is missing"),
+            DownloadError::Expired => write!(f, "file download link has expired"),
+            DownloadError::TooLarge { size, limit } => write!(
+                f,
+                "file size of {} bytes exceeds the download limit of {} bytes",
+                size, limit
+            ),
+            DownloadError::UnexpectedStatus(status) => {
+                write!(f, "unexpected download status code: {}", status)
+            }
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.0 {
@@ -52,9 +280,103 @@ impl fmt::Display for Error {
             ErrorKind::Hyper(error) => write!(f, "{}", error),
             ErrorKind::Http(error) => write!(f, "{}", error),
             ErrorKind::Io(error) => write!(f, "{}", error),
+            ErrorKind::Json(error) => write!(f, "{}", error),
             ErrorKind::InvalidMultipartFilename => write!(f, "invalid multipart filename"),
+            ErrorKind::FileStreamConsumed => write!(f, "file stream was already sent"),
+            ErrorKind::Download(error) => write!(f, "{}", error),
+            ErrorKind::DownloadUnsupported => write!(f, "connector does not support downloads"),
+            ErrorKind::ApiUrlUnsupported => {
+                write!(f, "connector does not support custom Bot API server URLs")
+            }
+            ErrorKind::UnexpectedRequest(request) => {
+                write!(f, "no mocked response for request: {}", request)
+            }
         }
     }
 }
 
 impl error::Error for Error {}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.method {
+            Some(ref method) => write!(f, "{}", method)?,
+            None => write!(f, "request")?,
+        }
+        if let Some(ref chat) = self.chat {
+            write!(f, " to chat {}", chat)?;
+        }
+        write!(f, " failed: {}", self.error)
+    }
+}
+
+impl error::Error for SpawnError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{GetMe, SendMessage, UserId};
+
+    use super::{Error, ErrorClass};
+    use crate::connector::MockConnector;
+    use crate::Api;
+
+    async fn send_error(response: &str) -> Error {
+        let mock = MockConnector::new();
+        mock.respond("sendMessage", response);
+        let api = Api::with_connector("token", Box::new(mock));
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn test_error_class() {
+        let error =
+            send_error(r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was blocked by the user"}"#)
+                .await;
+        assert_eq!(error.class(), ErrorClass::BotBlocked);
+        assert_eq!(error.error_code(), Some(403));
+        assert_eq!(
+            error.description(),
+            Some("Forbidden: bot was blocked by the user")
+        );
+
+        let error = send_error(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: chat not found"}"#,
+        )
+        .await;
+        assert_eq!(error.class(), ErrorClass::ChatNotFound);
+
+        let error = send_error(r#"{"ok":false,"error_code":400,"description":"Bad Request: message is not modified: specified new message content and reply markup are exactly the same"}"#).await;
+        assert_eq!(error.class(), ErrorClass::MessageNotModified);
+
+        let error = send_error(r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1001113717682}}"#).await;
+        assert_eq!(error.class(), ErrorClass::ChatMigrated);
+        assert_eq!(error.migrate_to_chat_id(), Some(-1001113717682));
+
+        let error = send_error(r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}"#).await;
+        assert_eq!(error.class(), ErrorClass::FloodControl);
+        assert_eq!(error.retry_after(), Some(5));
+
+        let error = send_error(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: message text is empty"}"#,
+        )
+        .await;
+        assert_eq!(error.class(), ErrorClass::Telegram);
+        assert_eq!(error.error_code(), Some(400));
+
+        let error = send_error(r#"{"ok":true"#).await;
+        assert_eq!(error.class(), ErrorClass::Json);
+        assert!(error.is_json());
+        assert!(!error.is_network());
+        assert_eq!(error.error_code(), None);
+
+        let api = Api::with_connector("token", Box::new(MockConnector::new()));
+        let error = api.send(GetMe).await.unwrap_err();
+        assert_eq!(error.class(), ErrorClass::Other);
+    }
+}
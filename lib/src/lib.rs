@@ -2,17 +2,32 @@
 //! See [readme](https://github.com/telegram-rs/telegram-bot) for details.
 
 mod api;
+mod broadcast;
+mod chat_param;
 mod errors;
 mod macros;
+mod migration;
+mod offset;
+mod rate_limit;
+mod retry;
+mod shutdown;
 mod stream;
+mod webhook;
 
 pub mod connector;
 pub mod prelude;
 pub mod types;
 pub mod util;
 
-pub use self::api::Api;
-pub use self::errors::Error;
+pub use self::api::{Api, ApiBuilder};
+pub use self::broadcast::{LagPolicy, SubscribeHandle, Subscription, UpdatesBroadcast};
+pub use self::errors::{DownloadError, Error, ErrorClass, SpawnError};
+pub use offset::{Acknowledger, FileOffsetStore, MemoryOffsetStore, OffsetStore};
 pub use prelude::*;
-pub use stream::UpdatesStream;
+pub use rate_limit::RateLimiter;
+pub use retry::RetryPolicy;
+pub use shutdown::ShutdownHandle;
+pub use stream::{BackoffPolicy, UpdateKindStream, UpdatesStream};
 pub use types::*;
+pub use util::rich_text::RichText;
+pub use webhook::WebhookServer;
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use telegram_bot_raw::{ChatId, HttpRequest, MessageKind, Update, UpdateKind};
+
+use crate::chat_param::{request_chat_id, with_chat_id};
+
+pub(crate) type MigrationHook = Arc<dyn Fn(ChatId, ChatId) + Send + Sync>;
+
+/// Map from migrated groups to their new supergroups.
+pub(crate) struct ChatMigrations {
+    chats: Mutex<HashMap<ChatId, ChatId>>,
+    hook: Option<MigrationHook>,
+}
+
+impl ChatMigrations {
+    pub(crate) fn new(hook: Option<MigrationHook>) -> Self {
+        ChatMigrations {
+            chats: Mutex::new(HashMap::new()),
+            hook,
+        }
+    }
+
+    pub(crate) fn get(&self, chat: ChatId) -> Option<ChatId> {
+        self.chats.lock().unwrap().get(&chat).cloned()
+    }
+
+    /// Remember that the group `from` was migrated to the supergroup `to`,
+    /// the hook is only called for new migrations.
+    pub(crate) fn insert(&self, from: ChatId, to: ChatId) {
+        let previous = self.chats.lock().unwrap().insert(from, to);
+        if previous != Some(to) {
+            tracing::debug!(from = %from, to = %to, "chat migrated");
+            if let Some(ref hook) = self.hook {
+                hook(from, to);
+            }
+        }
+    }
+
+    pub(crate) fn learn(&self, update: &Update) {
+        let message = match update.kind {
+            UpdateKind::Message(ref message) => message,
+            _ => return,
+        };
+        match message.kind {
+            MessageKind::MigrateToChatId { data } => {
+                self.insert(message.chat.id(), ChatId::new(data))
+            }
+            MessageKind::MigrateFromChatId { data } => {
+                self.insert(ChatId::new(data), message.chat.id())
+            }
+            _ => (),
+        }
+    }
+
+    /// Send requests to migrated groups to their supergroups instead.
+    pub(crate) fn rewrite(&self, request: HttpRequest) -> HttpRequest {
+        match request_chat_id(&request.body).and_then(|chat| self.get(ChatId::new(chat))) {
+            Some(chat) => with_chat_id(request, chat),
+            None => request,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use telegram_bot_raw::{ChatId, GroupId, SendMessage, Update};
+
+    use crate::connector::MockConnector;
+    use crate::Api;
+
+    const MIGRATE_TO_CHAT_ID: &str =
+        include_str!("../../raw/tests/update_assets/migrate_to_chat_id.json");
+    const MIGRATED: &str = r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1002}}"#;
+    const MIGRATED_BACK: &str = r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1}}"#;
+    const SEND_MESSAGE: &str = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":-1002,"type":"supergroup","title":"group"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+
+    #[tokio::test]
+    async fn test_migrate_on_error() {
+        let mock = MockConnector::new();
+        mock.respond_if("sendMessage", |body| body["chat_id"] == -1, MIGRATED)
+            .respond("sendMessage", SEND_MESSAGE);
+        let migrations = Arc::new(Mutex::new(Vec::new()));
+        let hook_migrations = migrations.clone();
+        let api = Api::builder("token")
+            .connector(Box::new(mock.clone()))
+            .on_chat_migrated(move |from, to| hook_migrations.lock().unwrap().push((from, to)))
+            .build();
+
+        let group = GroupId::new(-1);
+        api.send(SendMessage::new(group, "hi")).await.unwrap();
+        api.send(SendMessage::new(group, "hi")).await.unwrap();
+
+        let chat_ids: Vec<_> = mock
+            .request_bodies("sendMessage")
+            .iter()
+            .map(|body| body["chat_id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(chat_ids, vec![-1, -1002, -1002]);
+        assert_eq!(
+            *migrations.lock().unwrap(),
+            vec![(ChatId::new(-1), ChatId::new(-1002))]
+        );
+        assert_eq!(
+            api.migrated_chat_id(ChatId::new(-1)),
+            Some(ChatId::new(-1002))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_once() {
+        let mock = MockConnector::new();
+        mock.respond_if("sendMessage", |body| body["chat_id"] == -1, MIGRATED)
+            .respond_if(
+                "sendMessage",
+                |body| body["chat_id"] == -1002,
+                MIGRATED_BACK,
+            );
+        let api = Api::builder("token")
+            .connector(Box::new(mock.clone()))
+            .follow_chat_migrations(true)
+            .build();
+
+        let error = api
+            .send(SendMessage::new(GroupId::new(-1), "hi"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.migrate_to_chat_id(), Some(-1));
+
+        let chat_ids: Vec<_> = mock
+            .request_bodies("sendMessage")
+            .iter()
+            .map(|body| body["chat_id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(chat_ids, vec![-1, -1002]);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_from_update() {
+        let api = Api::builder("token")
+            .connector(Box::new(MockConnector::new()))
+            .follow_chat_migrations(true)
+            .build();
+        let update: Update = serde_json::from_str(MIGRATE_TO_CHAT_ID).unwrap();
+        api.learn_migration(&update);
+        assert_eq!(
+            api.migrated_chat_id(ChatId::new(-216055857)),
+            Some(ChatId::new(-1001113717682))
+        );
+    }
+}
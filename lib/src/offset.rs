@@ -0,0 +1,457 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+
+use telegram_bot_raw::Integer;
+
+use crate::errors::{Error, ErrorKind};
+
+/// Storage for the id of the last handled update, so that
+/// [`UpdatesStream`](struct.UpdatesStream.html) continues where it stopped after a restart,
+/// see [`UpdatesStream::offset_store`](struct.UpdatesStream.html#method.offset_store).
+pub trait OffsetStore: Send {
+    /// Load the id of the last handled update, `None` if nothing was stored yet.
+    fn load(&self) -> io::Result<Option<Integer>>;
+
+    /// Store the id of the last handled update.
+    fn store(&self, update_id: Integer) -> io::Result<()>;
+
+    /// Whether `store` blocks, e.g. on file or network IO.
+    ///
+    /// Blocking stores run on a blocking thread and consecutive offsets are coalesced,
+    /// so only the latest one is stored. Defaults to `true`.
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+/// Offset store which keeps the offset in memory.
+///
+/// Clones share the offset, so it survives recreating the stream, but not a restart.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryOffsetStore(Arc<Mutex<Option<Integer>>>);
+
+impl MemoryOffsetStore {
+    /// Create an empty `MemoryOffsetStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id of the last handled update.
+    pub fn offset(&self) -> Option<Integer> {
+        *self.0.lock().unwrap()
+    }
+}
+
+impl OffsetStore for MemoryOffsetStore {
+    fn load(&self) -> io::Result<Option<Integer>> {
+        Ok(self.offset())
+    }
+
+    fn store(&self, update_id: Integer) -> io::Result<()> {
+        *self.0.lock().unwrap() = Some(update_id);
+        Ok(())
+    }
+
+    fn is_blocking(&self) -> bool {
+        false
+    }
+}
+
+/// Offset store which keeps the offset in a file.
+///
+/// The file is replaced atomically and synced to disk, so a crash while storing
+/// leaves the previous offset.
+#[derive(Debug, Clone)]
+pub struct FileOffsetStore {
+    path: PathBuf,
+}
+
+impl FileOffsetStore {
+    /// Create a `FileOffsetStore` which keeps the offset in the file at `path`.
+    /// The file is created when the first offset is stored.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileOffsetStore { path: path.into() }
+    }
+}
+
+impl OffsetStore for FileOffsetStore {
+    fn load(&self) -> io::Result<Option<Integer>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn store(&self, update_id: Integer) -> io::Result<()> {
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        {
+            let mut file = std::fs::File::create(&temporary)?;
+            file.write_all(update_id.to_string().as_bytes())?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&temporary, &self.path)?;
+
+        // Sync the directory as well, otherwise the rename may be lost on a crash.
+        #[cfg(unix)]
+        {
+            let directory = match self.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            std::fs::File::open(directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Handle for acknowledging handled updates, see
+/// [`UpdatesStream::acknowledger`](struct.UpdatesStream.html#method.acknowledger).
+#[derive(Clone)]
+pub struct Acknowledger(pub(crate) Arc<Acks>);
+
+impl Acknowledger {
+    /// Confirm that the update with `update_id` was handled.
+    ///
+    /// The stored offset advances past all updates which were received before it
+    /// and were acknowledged, so updates are handled at least once even if they
+    /// are acknowledged out of order.
+    pub fn ack(&self, update_id: Integer) -> Result<(), Error> {
+        self.0.ack(update_id)
+    }
+}
+
+impl fmt::Debug for Acknowledger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0.state.lock().unwrap();
+        f.debug_struct("Acknowledger")
+            .field("pending", &state.pending)
+            .field("committed", &state.committed)
+            .finish()
+    }
+}
+
+/// Bookkeeping of delivered and acknowledged updates shared by a stream and its acknowledgers.
+#[derive(Default)]
+pub(crate) struct Acks {
+    state: Mutex<AckState>,
+    all_acked: Notify,
+}
+
+#[derive(Default)]
+struct AckState {
+    pending: BTreeSet<Integer>,
+    acked: BTreeSet<Integer>,
+    delivered: Integer,
+    committed: Integer,
+    writer: Option<Arc<OffsetWriter>>,
+}
+
+/// Writes offsets to a store, blocking stores are written on a blocking thread.
+struct OffsetWriter {
+    store: Mutex<Box<dyn OffsetStore>>,
+    blocking: bool,
+    state: Mutex<WriterState>,
+    idle: Notify,
+}
+
+#[derive(Default)]
+struct WriterState {
+    /// Latest offset which still has to be stored.
+    next: Option<Integer>,
+    running: bool,
+    /// Error of a background write, returned by the next write.
+    error: Option<io::Error>,
+}
+
+impl OffsetWriter {
+    fn new(store: Box<dyn OffsetStore>) -> Self {
+        OffsetWriter {
+            blocking: store.is_blocking(),
+            store: Mutex::new(store),
+            state: Mutex::new(WriterState::default()),
+            idle: Notify::new(),
+        }
+    }
+
+    fn load(&self) -> io::Result<Option<Integer>> {
+        self.store.lock().unwrap().load()
+    }
+
+    fn write(self: &Arc<Self>, offset: Integer) -> io::Result<()> {
+        let handle = match Handle::try_current() {
+            Ok(handle) if self.blocking => handle,
+            _ => return self.store.lock().unwrap().store(offset),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.next = Some(offset);
+        if !state.running {
+            state.running = true;
+            let writer = self.clone();
+            handle.spawn_blocking(move || writer.run());
+        }
+        match state.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn run(&self) {
+        loop {
+            let offset = {
+                let mut state = self.state.lock().unwrap();
+                match state.next.take() {
+                    Some(offset) => offset,
+                    None => {
+                        state.running = false;
+                        self.idle.notify_waiters();
+                        return;
+                    }
+                }
+            };
+            if let Err(err) = self.store.lock().unwrap().store(offset) {
+                tracing::error!(error = %err, "unable to store update offset");
+                self.state.lock().unwrap().error = Some(err);
+            }
+        }
+    }
+
+    /// Wait until all offsets are written.
+    async fn flush(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if !self.state.lock().unwrap().running {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl Acks {
+    /// Use `store` for the offset and load the stored offset.
+    pub(crate) fn set_store(&self, store: Box<dyn OffsetStore>) -> Result<Option<Integer>, Error> {
+        let writer = OffsetWriter::new(store);
+        let offset = writer.load().map_err(ErrorKind::from)?;
+        let mut state = self.state.lock().unwrap();
+        if let Some(offset) = offset {
+            state.delivered = state.delivered.max(offset);
+            state.committed = state.committed.max(offset);
+        }
+        state.writer = Some(Arc::new(writer));
+        Ok(offset)
+    }
+
+    /// Record that an update was yielded by the stream.
+    pub(crate) fn deliver(&self, update_id: Integer, manual_ack: bool) -> Result<(), Error> {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.delivered = state.delivered.max(update_id);
+            state.pending.insert(update_id);
+            if manual_ack {
+                return Ok(());
+            }
+        }
+        self.ack(update_id)
+    }
+
+    pub(crate) fn ack(&self, update_id: Integer) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if !state.pending.remove(&update_id) {
+            return Ok(());
+        }
+        state.acked.insert(update_id);
+
+        // Commit the last acknowledged update received before the first pending one.
+        let committed = match state.pending.iter().next().cloned() {
+            Some(first_pending) => match state.acked.range(..first_pending).next_back() {
+                Some(&committed) => committed,
+                None => return Ok(()),
+            },
+            None => {
+                self.all_acked.notify_one();
+                state.delivered
+            }
+        };
+        state.acked = state.acked.split_off(&(committed + 1));
+        if committed <= state.committed {
+            return Ok(());
+        }
+
+        state.committed = committed;
+        let writer = match state.writer {
+            Some(ref writer) => writer.clone(),
+            None => return Ok(()),
+        };
+        drop(state);
+
+        tracing::trace!(offset = committed, "storing update offset");
+        writer
+            .write(committed)
+            .map_err(|err| ErrorKind::from(err).into())
+    }
+
+    /// Id of the last handled update.
+    pub(crate) fn committed(&self) -> Integer {
+        self.state.lock().unwrap().committed
+    }
+
+    /// Wait until the stored offset is written.
+    pub(crate) async fn flush(&self) {
+        let writer = self.state.lock().unwrap().writer.clone();
+        if let Some(writer) = writer {
+            writer.flush().await;
+        }
+    }
+
+    /// Wait until all delivered updates are acknowledged.
+    pub(crate) async fn wait_all_acked(&self) {
+        loop {
+            if self.state.lock().unwrap().pending.is_empty() {
+                return;
+            }
+            self.all_acked.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, ThreadId};
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use telegram_bot_raw::Integer;
+    use tokio::time::timeout;
+
+    use super::{FileOffsetStore, MemoryOffsetStore, OffsetStore};
+    use crate::connector::MockConnector;
+    use crate::Api;
+
+    fn updates(ids: &[i64]) -> String {
+        let updates: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"update_id":{},"message":{{"message_id":{},"date":0,"chat":{{"id":1,"type":"private","first_name":"user"}},"from":{{"id":1,"is_bot":false,"first_name":"user"}},"text":"hi"}}}}"#,
+                    id, id
+                )
+            })
+            .collect();
+        format!(r#"{{"ok":true,"result":[{}]}}"#, updates.join(","))
+    }
+
+    #[test]
+    fn test_file_offset_store() {
+        let path = std::env::temp_dir().join("telegram-bot-offset-test");
+        let _ = std::fs::remove_file(&path);
+
+        let store = FileOffsetStore::new(&path);
+        assert_eq!(store.load().unwrap(), None);
+        store.store(42).unwrap();
+        assert_eq!(FileOffsetStore::new(&path).load().unwrap(), Some(42));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_manual_ack() {
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", updates(&[10, 11]))
+            .respond_once("getUpdates", updates(&[12]))
+            .respond_once("getUpdates", updates(&[13]));
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+        let store = MemoryOffsetStore::new();
+
+        let mut stream = api.stream();
+        stream.offset_store(store.clone()).unwrap().manual_ack(true);
+        let acknowledger = stream.acknowledger();
+
+        let first = stream.next().await.unwrap().unwrap();
+        let second = stream.next().await.unwrap().unwrap();
+        acknowledger.ack(second.id).unwrap();
+        assert_eq!(store.offset(), None);
+
+        // The next batch isn't requested until every update is acknowledged.
+        assert!(timeout(Duration::from_secs(60), stream.next())
+            .await
+            .is_err());
+        assert_eq!(mock.request_bodies("getUpdates").len(), 1);
+
+        acknowledger.ack(first.id).unwrap();
+        assert_eq!(store.offset(), Some(11));
+        let third = stream.next().await.unwrap().unwrap();
+        assert_eq!(third.id, 12);
+        assert_eq!(store.offset(), Some(11));
+        drop(stream);
+
+        // A new stream continues after the last acknowledged update.
+        let mut stream = api.stream();
+        stream.offset_store(store.clone()).unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 13);
+        assert_eq!(store.offset(), Some(13));
+
+        let offsets: Vec<_> = mock
+            .request_bodies("getUpdates")
+            .iter()
+            .map(|body| body["offset"].as_i64().unwrap())
+            .collect();
+        assert_eq!(offsets, vec![1, 12, 12]);
+    }
+
+    /// Records the stored offsets with the thread they were stored on.
+    #[derive(Clone, Default)]
+    struct ThreadStore(Arc<Mutex<Vec<(Integer, ThreadId)>>>);
+
+    impl OffsetStore for ThreadStore {
+        fn load(&self) -> io::Result<Option<Integer>> {
+            Ok(None)
+        }
+
+        fn store(&self, update_id: Integer) -> io::Result<()> {
+            thread::sleep(Duration::from_millis(10));
+            self.0
+                .lock()
+                .unwrap()
+                .push((update_id, thread::current().id()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocking_store() {
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", updates(&[10, 11, 12]))
+            .respond("getUpdates", updates(&[]));
+        let api = Api::with_connector("token", Box::new(mock));
+        let store = ThreadStore::default();
+
+        let mut stream = api.stream();
+        stream.offset_store(store.clone()).unwrap();
+        for _ in 0..3 {
+            stream.next().await.unwrap().unwrap();
+        }
+        stream.shutdown_handle().shutdown();
+        assert!(stream.next().await.is_none());
+
+        // The stream waits for the last offset to be stored before it ends.
+        let stored = store.0.lock().unwrap();
+        assert_eq!(stored.last().unwrap().0, 12);
+        assert!(stored.iter().all(|(_, id)| *id != thread::current().id()));
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use telegram_bot_raw::{ChatRef, HttpRequest, Integer};
+use tokio::time::{sleep_until, Instant};
+
+use crate::chat_param::request_chat;
+
+const RATE_LIMIT_GLOBAL_MESSAGES: usize = 30;
+const RATE_LIMIT_GLOBAL_PERIOD_SECONDS: u64 = 1;
+const RATE_LIMIT_PRIVATE_CHAT_MESSAGES: usize = 1;
+const RATE_LIMIT_PRIVATE_CHAT_PERIOD_SECONDS: u64 = 1;
+const RATE_LIMIT_GROUP_MESSAGES: usize = 20;
+const RATE_LIMIT_GROUP_PERIOD_SECONDS: u64 = 60;
+
+/// Limiter for outgoing messages, see
+/// [`ApiBuilder::rate_limiter`](struct.ApiBuilder.html#method.rate_limiter).
+///
+/// Requests sending messages are delayed to stay within Telegram's limits,
+/// which are by default:
+///
+/// * 30 messages per second in total,
+/// * 1 message per second to the same private chat,
+/// * 20 messages per minute to the same group, supergroup or channel.
+///
+/// The target chat is taken from the `chat_id` parameter of the request.
+/// Other methods, such as `getChat`, are never delayed.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telegram_bot::{Api, RateLimiter};
+///
+/// # fn main() {
+/// # let telegram_token = "token";
+/// let limiter = RateLimiter::new().global_limit(25, Duration::from_secs(1));
+/// let api = Api::builder(telegram_token).rate_limiter(limiter).build();
+/// # }
+/// ```
+pub struct RateLimiter {
+    global: Limit,
+    private_chat: Limit,
+    group: Limit,
+    limited: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    messages: usize,
+    period: Duration,
+}
+
+fn is_private(chat: &ChatRef) -> bool {
+    match chat {
+        ChatRef::Id(id) => Integer::from(*id) > 0,
+        _ => false,
+    }
+}
+
+struct RateLimiterState {
+    global: Window,
+    chats: HashMap<ChatRef, Window>,
+}
+
+/// Sliding window with the times of already reserved messages.
+#[derive(Default)]
+struct Window {
+    reserved: VecDeque<Instant>,
+}
+
+impl Window {
+    /// Reserve the earliest time not before `now` at which one more message fits
+    /// into the limit. Reservations are made in increasing order, so the window stays sorted.
+    fn reserve(&mut self, limit: Limit, now: Instant) -> Instant {
+        while let Some(&time) = self.reserved.front() {
+            if time + limit.period > now {
+                break;
+            }
+            self.reserved.pop_front();
+        }
+
+        let mut time = now;
+        if limit.messages == 0 {
+            return time;
+        }
+        if self.reserved.len() >= limit.messages {
+            let oldest = self.reserved[self.reserved.len() - limit.messages];
+            time = time.max(oldest + limit.period);
+        }
+        self.reserved.push_back(time);
+        time
+    }
+
+    fn is_expired(&self, limit: Limit, now: Instant) -> bool {
+        match self.reserved.back() {
+            Some(&time) => time + limit.period <= now,
+            None => true,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create a new `RateLimiter` with Telegram's default limits.
+    pub fn new() -> Self {
+        RateLimiter {
+            global: Limit {
+                messages: RATE_LIMIT_GLOBAL_MESSAGES,
+                period: Duration::from_secs(RATE_LIMIT_GLOBAL_PERIOD_SECONDS),
+            },
+            private_chat: Limit {
+                messages: RATE_LIMIT_PRIVATE_CHAT_MESSAGES,
+                period: Duration::from_secs(RATE_LIMIT_PRIVATE_CHAT_PERIOD_SECONDS),
+            },
+            group: Limit {
+                messages: RATE_LIMIT_GROUP_MESSAGES,
+                period: Duration::from_secs(RATE_LIMIT_GROUP_PERIOD_SECONDS),
+            },
+            limited: Arc::new(|method| {
+                (method.starts_with("send") && method != "sendChatAction")
+                    || method.starts_with("forward")
+                    || method.starts_with("copy")
+            }),
+            state: Mutex::new(RateLimiterState {
+                global: Window::default(),
+                chats: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Allow at most `messages` messages per `period` in total.
+    ///
+    /// Defaults to 30 messages per second.
+    pub fn global_limit(mut self, messages: usize, period: Duration) -> Self {
+        self.global = Limit { messages, period };
+        self
+    }
+
+    /// Allow at most `messages` messages per `period` to the same private chat.
+    ///
+    /// Defaults to 1 message per second.
+    pub fn private_chat_limit(mut self, messages: usize, period: Duration) -> Self {
+        self.private_chat = Limit { messages, period };
+        self
+    }
+
+    /// Allow at most `messages` messages per `period` to the same group, supergroup or channel.
+    ///
+    /// Defaults to 20 messages per minute.
+    pub fn group_limit(mut self, messages: usize, period: Duration) -> Self {
+        self.group = Limit { messages, period };
+        self
+    }
+
+    /// Set the methods which are rate limited, e.g. `"sendMessage"`.
+    ///
+    /// Defaults to all `send*`, `forward*` and `copy*` methods except `sendChatAction`.
+    pub fn limited_methods(self, methods: &[&str]) -> Self {
+        let methods: Vec<String> = methods.iter().map(|method| method.to_string()).collect();
+        self.limit_if(move |method| methods.iter().any(|m| m == method))
+    }
+
+    /// Decide which methods are rate limited with a predicate on the method name.
+    pub fn limit_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.limited = Arc::new(predicate);
+        self
+    }
+
+    /// Wait until the request can be sent without exceeding the limits.
+    pub(crate) async fn acquire(&self, request: &HttpRequest) {
+        if !(self.limited)(request.name_str()) {
+            return;
+        }
+
+        if let Some(chat) = request_chat(&request.body) {
+            let time = self.reserve_chat(chat);
+            if time > Instant::now() {
+                tracing::trace!(name = %request.name_str(), "waiting for per-chat rate limit");
+                sleep_until(time).await;
+            }
+        }
+
+        let time = self.reserve_global();
+        if time > Instant::now() {
+            tracing::trace!(name = %request.name_str(), "waiting for global rate limit");
+            sleep_until(time).await;
+        }
+    }
+
+    fn reserve_chat(&self, chat: ChatRef) -> Instant {
+        let now = Instant::now();
+        let limit = if is_private(&chat) {
+            self.private_chat
+        } else {
+            self.group
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if !state.chats.contains_key(&chat) {
+            let (private_chat, group) = (self.private_chat, self.group);
+            state.chats.retain(|chat, window| {
+                let limit = if is_private(chat) {
+                    private_chat
+                } else {
+                    group
+                };
+                !window.is_expired(limit, now)
+            });
+        }
+        state.chats.entry(chat).or_default().reserve(limit, now)
+    }
+
+    fn reserve_global(&self) -> Instant {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.global.reserve(self.global, now)
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("global", &self.global)
+            .field("private_chat", &self.private_chat)
+            .field("group", &self.group)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use futures::future::join_all;
+    use futures::Future;
+    use telegram_bot_raw::{
+        GetChat, GroupId, HttpRequest, HttpResponse, InputFileRef, SendMessage, SendPhoto, UserId,
+    };
+    use tokio::time::Instant;
+
+    use super::RateLimiter;
+    use crate::connector::Connector;
+    use crate::errors::Error;
+    use crate::Api;
+
+    const GET_CHAT: &str = r#"{"ok":true,"result":{"id":1,"type":"private","first_name":"user"}}"#;
+    const SEND_MESSAGE: &str = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+
+    type Requests = Arc<Mutex<Vec<(String, Duration)>>>;
+
+    /// Records the time of every request.
+    #[derive(Debug)]
+    struct TimingConnector {
+        start: Instant,
+        requests: Requests,
+    }
+
+    impl Connector for TimingConnector {
+        fn request(
+            &self,
+            _token: &str,
+            req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            let name = req.name_str();
+            self.requests
+                .lock()
+                .unwrap()
+                .push((name.to_string(), self.start.elapsed()));
+            let body = if name == "getChat" {
+                GET_CHAT
+            } else {
+                SEND_MESSAGE
+            };
+            Box::pin(async move {
+                Ok(HttpResponse {
+                    body: Some(body.as_bytes().to_vec()),
+                })
+            })
+        }
+    }
+
+    fn timed_api(limiter: RateLimiter) -> (Api, Requests) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let connector = TimingConnector {
+            start: Instant::now(),
+            requests: requests.clone(),
+        };
+        let api = Api::builder("token")
+            .connector(Box::new(connector))
+            .rate_limiter(limiter)
+            .build();
+        (api, requests)
+    }
+
+    fn seconds(requests: &Requests) -> Vec<u64> {
+        let requests = requests.lock().unwrap();
+        requests
+            .iter()
+            .map(|(_, elapsed)| elapsed.as_secs())
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_private_chat_limit() {
+        let (api, requests) = timed_api(RateLimiter::new());
+        let chat = UserId::new(1);
+        join_all((0..3).map(|_| api.send(SendMessage::new(chat, "hi")))).await;
+        api.send(GetChat::new(chat)).await.unwrap();
+        api.send(SendPhoto::new(chat, InputFileRef::new("file_id")))
+            .await
+            .unwrap();
+
+        assert_eq!(seconds(&requests), vec![0, 1, 2, 2, 3]);
+        assert_eq!(requests.lock().unwrap()[3].0, "getChat");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_group_limit() {
+        let (api, requests) = timed_api(RateLimiter::new());
+        let chat = GroupId::new(-1);
+        join_all((0..25).map(|_| api.send(SendMessage::new(chat, "hi")))).await;
+
+        let seconds = seconds(&requests);
+        assert_eq!(&seconds[..20], &[0; 20][..]);
+        assert_eq!(&seconds[20..], &[60; 5][..]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_global_limit() {
+        let limiter = RateLimiter::new().global_limit(10, Duration::from_secs(1));
+        let (api, requests) = timed_api(limiter);
+        join_all((1..=25).map(|id| api.send(SendMessage::new(UserId::new(id), "hi")))).await;
+
+        let seconds = seconds(&requests);
+        assert_eq!(&seconds[..10], &[0; 10][..]);
+        assert_eq!(&seconds[10..20], &[1; 10][..]);
+        assert_eq!(&seconds[20..], &[2; 5][..]);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::errors::Error;
+
+const RETRY_MAX_ATTEMPTS: usize = 3;
+const RETRY_MAX_TOTAL_WAIT_SECONDS: u64 = 60;
+const RETRY_INITIAL_BACKOFF_MILLISECONDS: u64 = 500;
+const RETRY_MAX_BACKOFF_SECONDS: u64 = 30;
+
+/// Policy for automatically retrying failed requests, see
+/// [`ApiBuilder::retry_policy`](struct.ApiBuilder.html#method.retry_policy).
+///
+/// Two kinds of failures are retried:
+///
+/// * Flood control errors. Telegram rejected the request without processing it,
+///   so it is repeated after waiting for `retry_after` seconds.
+/// * Transient network errors, such as a refused or dropped connection. The request
+///   may have been processed already, so only retry-safe methods are repeated, with
+///   exponential backoff between attempts.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telegram_bot::{Api, RetryPolicy};
+///
+/// # fn main() {
+/// # let telegram_token = "token";
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .max_total_wait(Duration::from_secs(120))
+///     .retry_safe_methods(&["getMe", "getChat", "sendChatAction"]);
+/// let api = Api::builder(telegram_token).retry_policy(policy).build();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    max_total_wait: Duration,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_safe: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+}
+
+impl RetryPolicy {
+    /// Create a new `RetryPolicy` with default settings.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: RETRY_MAX_ATTEMPTS,
+            max_total_wait: Duration::from_secs(RETRY_MAX_TOTAL_WAIT_SECONDS),
+            initial_backoff: Duration::from_millis(RETRY_INITIAL_BACKOFF_MILLISECONDS),
+            max_backoff: Duration::from_secs(RETRY_MAX_BACKOFF_SECONDS),
+            retry_safe: Arc::new(|method| method.starts_with("get")),
+        }
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    ///
+    /// Defaults to 3.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the maximum time spent waiting between attempts of a single request.
+    /// A request isn't retried if the next delay would exceed this limit.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn max_total_wait(mut self, max_total_wait: Duration) -> Self {
+        self.max_total_wait = max_total_wait;
+        self
+    }
+
+    /// Set the delay before the first retry after a network error,
+    /// the delay doubles with every following attempt.
+    ///
+    /// Defaults to 500 ms.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the maximum delay between retries after network errors.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the methods which are retried after network errors, e.g. `"getChat"`.
+    ///
+    /// Defaults to all `get*` methods.
+    pub fn retry_safe_methods(self, methods: &[&str]) -> Self {
+        let methods: HashSet<String> = methods.iter().map(|method| method.to_string()).collect();
+        self.retry_safe_if(move |method| methods.contains(method))
+    }
+
+    /// Decide which methods are retried after network errors with a predicate
+    /// on the method name.
+    pub fn retry_safe_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.retry_safe = Arc::new(predicate);
+        self
+    }
+
+    /// Returns the delay before the next attempt, or `None` if the request
+    /// shouldn't be retried. `attempt` is the number of the failed attempt, starting at 1.
+    pub(crate) fn retry_delay(
+        &self,
+        method: &str,
+        error: &Error,
+        attempt: usize,
+        waited: Duration,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let delay = if let Some(seconds) = error.retry_after() {
+            Duration::from_secs(seconds.max(0) as u64)
+        } else if error.is_network() && (self.retry_safe)(method) {
+            let exponent = (attempt - 1).min(31) as u32;
+            self.initial_backoff
+                .checked_mul(1 << exponent)
+                .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+        } else {
+            return None;
+        };
+
+        if waited + delay > self.max_total_wait {
+            return None;
+        }
+        Some(delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("max_total_wait", &self.max_total_wait)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::Future;
+    use telegram_bot_raw::{GetMe, HttpRequest, HttpResponse, SendMessage, UserId};
+    use tokio::time::Instant;
+
+    use super::RetryPolicy;
+    use crate::connector::Connector;
+    use crate::errors::{Error, ErrorKind};
+    use crate::Api;
+
+    const FLOOD_WAIT: &str = r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}"#;
+    const GET_ME: &str =
+        r#"{"ok":true,"result":{"id":1,"is_bot":true,"first_name":"bot","username":"bot"}}"#;
+    const SEND_MESSAGE: &str = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+
+    /// Fails the first `failures` requests, then responds with `response`.
+    #[derive(Debug)]
+    struct FlakyConnector {
+        failures: usize,
+        flood_wait: bool,
+        response: &'static str,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl Connector for FlakyConnector {
+        fn request(
+            &self,
+            _token: &str,
+            _req: HttpRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            let attempt = self.requests.fetch_add(1, Ordering::SeqCst);
+            let result = if attempt >= self.failures {
+                Ok(self.response)
+            } else if self.flood_wait {
+                Ok(FLOOD_WAIT)
+            } else {
+                Err(())
+            };
+            Box::pin(async move {
+                match result {
+                    Ok(body) => Ok(HttpResponse {
+                        body: Some(body.as_bytes().to_vec()),
+                    }),
+                    Err(()) => Err(connection_error().await),
+                }
+            })
+        }
+    }
+
+    async fn connection_error() -> Error {
+        let error = hyper::Client::new()
+            .get("http://127.0.0.1:1".parse().unwrap())
+            .await
+            .unwrap_err();
+        ErrorKind::from(error).into()
+    }
+
+    fn flaky_api(
+        failures: usize,
+        flood_wait: bool,
+        response: &'static str,
+        policy: RetryPolicy,
+    ) -> (Api, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let connector = FlakyConnector {
+            failures,
+            flood_wait,
+            response,
+            requests: requests.clone(),
+        };
+        let api = Api::builder("token")
+            .connector(Box::new(connector))
+            .retry_policy(policy)
+            .build();
+        (api, requests)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flood_wait() {
+        let (api, requests) = flaky_api(2, true, SEND_MESSAGE, RetryPolicy::new());
+        let start = Instant::now();
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= Duration::from_secs(10));
+
+        let policy = RetryPolicy::new().max_total_wait(Duration::from_secs(7));
+        let (api, requests) = flaky_api(2, true, GET_ME, policy);
+        let error = api.send(GetMe).await.unwrap_err();
+        assert_eq!(error.retry_after(), Some(5));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_network_error() {
+        let (api, requests) = flaky_api(2, false, GET_ME, RetryPolicy::new());
+        api.send(GetMe).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let (api, requests) = flaky_api(1, false, SEND_MESSAGE, RetryPolicy::new());
+        assert!(api
+            .send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let policy = RetryPolicy::new().retry_safe_methods(&["sendMessage"]);
+        let (api, requests) = flaky_api(1, false, SEND_MESSAGE, policy);
+        api.send(SendMessage::new(UserId::new(1), "hi"))
+            .await
+            .unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_backoff() {
+        let policy = RetryPolicy::new()
+            .max_attempts(10)
+            .max_backoff(Duration::from_secs(3));
+        let error = connection_error().await;
+        let delays: Vec<_> = (1..6)
+            .map(|attempt| policy.retry_delay("getMe", &error, attempt, Duration::from_secs(0)))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(500)),
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(3)),
+                Some(Duration::from_secs(3)),
+            ]
+        );
+
+        let error = Error::from(ErrorKind::Io(std::io::ErrorKind::Other.into()));
+        assert_eq!(
+            policy.retry_delay("getMe", &error, 1, Duration::from_secs(0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::Notify;
+
+/// Handle for stopping an [`UpdatesStream`](struct.UpdatesStream.html) gracefully, see
+/// [`UpdatesStream::shutdown_handle`](struct.UpdatesStream.html#method.shutdown_handle).
+#[derive(Clone)]
+pub struct ShutdownHandle(pub(crate) Arc<ShutdownSignal>);
+
+impl ShutdownHandle {
+    /// Ask the stream to stop.
+    ///
+    /// The stream abandons the running long poll, yields the updates which were already
+    /// received, confirms the offset of the handled updates to Telegram and then ends.
+    pub fn shutdown(&self) {
+        self.0.requested.store(true, Ordering::SeqCst);
+        self.0.notify.notify_one();
+    }
+
+    /// Returns `true` if the shutdown was requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.0.is_requested()
+    }
+}
+
+impl fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownHandle")
+            .field("is_shutdown", &self.is_shutdown())
+            .finish()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ShutdownSignal {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl ShutdownSignal {
+    pub(crate) fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the shutdown is requested.
+    pub(crate) async fn requested(self: Arc<Self>) {
+        while !self.is_requested() {
+            self.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use telegram_bot_raw::{SendMessage, UserId};
+
+    use crate::connector::MockConnector;
+    use crate::{Api, RateLimiter};
+
+    const UPDATES: &str = r#"{"ok":true,"result":[
+        {"update_id":10,"message":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}},
+        {"update_id":11,"message":{"message_id":2,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":1,"is_bot":false,"first_name":"user"},"text":"hi"}}
+    ]}"#;
+    const NO_UPDATES: &str = r#"{"ok":true,"result":[]}"#;
+    const SEND_MESSAGE: &str = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}}"#;
+
+    #[tokio::test]
+    async fn test_stream_shutdown() {
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", UPDATES)
+            .respond("getUpdates", NO_UPDATES);
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+        let mut stream = api.stream();
+        let shutdown = stream.shutdown_handle();
+
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 10);
+        shutdown.shutdown();
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 11);
+        assert!(stream.next().await.is_none());
+        assert!(stream.next().await.is_none());
+
+        let bodies = mock.request_bodies("getUpdates");
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[1]["offset"], 12);
+        assert_eq!(bodies[1]["timeout"], 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain() {
+        let mock = MockConnector::new();
+        mock.respond("sendMessage", SEND_MESSAGE);
+        // Spawned messages to the same chat are one second apart.
+        let api = Api::builder("token")
+            .connector(Box::new(mock.clone()))
+            .rate_limiter(RateLimiter::new())
+            .build();
+
+        assert!(api.drain(Duration::from_secs(0)).await);
+        for _ in 0..3 {
+            api.spawn(SendMessage::new(UserId::new(1), "hi"));
+        }
+        assert!(!api.drain(Duration::from_millis(1500)).await);
+        assert!(api.drain(Duration::from_secs(5)).await);
+        assert_eq!(mock.request_bodies("sendMessage").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_drain_panicked() {
+        // No response for `sendMessage`, so the spawned request fails and the hook panics.
+        let mock = MockConnector::new();
+        let api = Api::builder("token")
+            .connector(Box::new(mock.clone()))
+            .on_spawn_error(|_| panic!("spawn error hook"))
+            .build();
+
+        let handle = api.spawn(SendMessage::new(UserId::new(1), "hi"));
+        assert!(handle.await.unwrap_err().is_panic());
+        assert!(api.drain(Duration::from_secs(5)).await);
+
+        let handle = api.spawn(SendMessage::new(UserId::new(1), "hi"));
+        handle.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+        assert!(api.drain(Duration::from_secs(5)).await);
+    }
+}
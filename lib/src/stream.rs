@@ -1,21 +1,116 @@
 use std::cmp::max;
 use std::collections::VecDeque;
+use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
 use std::time::Duration;
 
-use futures::Stream;
+use futures::{ready, Stream};
+use rand::Rng;
+use tokio::time::sleep;
 
-use telegram_bot_raw::{AllowedUpdate, GetUpdates, Integer, Update};
+use telegram_bot_raw::{
+    AllowedUpdate, CallbackQuery, GetUpdates, InlineQuery, Integer, Message, Update, UpdateKind,
+};
 
 use crate::api::Api;
 use crate::errors::Error;
+use crate::offset::{Acknowledger, Acks, OffsetStore};
+use crate::shutdown::{ShutdownHandle, ShutdownSignal};
 
 const TELEGRAM_LONG_POLL_TIMEOUT_SECONDS: u64 = 5;
 const TELEGRAM_LONG_POLL_LIMIT_MESSAGES: Integer = 100;
 const TELEGRAM_LONG_POLL_ERROR_DELAY_MILLISECONDS: u64 = 500;
+const TELEGRAM_LONG_POLL_MAX_ERROR_DELAY_SECONDS: u64 = 30;
+const TELEGRAM_LONG_POLL_ERROR_DELAY_JITTER: f64 = 0.2;
+
+/// Policy for delays after failed requests, see
+/// [`UpdatesStream::backoff`](struct.UpdatesStream.html#method.backoff).
+///
+/// The delay starts at the initial delay and doubles after every consecutive error,
+/// up to the maximum delay. Each delay is shortened by a random fraction up to
+/// the jitter, so that many bots don't retry in lockstep.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telegram_bot::{Api, BackoffPolicy};
+///
+/// # fn main() {
+/// # let api = Api::new("token");
+/// let mut stream = api.stream();
+/// stream.backoff(
+///     BackoffPolicy::new()
+///         .initial_delay(Duration::from_secs(1))
+///         .max_delay(Duration::from_secs(60)),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackoffPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+}
+
+impl BackoffPolicy {
+    /// Create a new `BackoffPolicy` with default settings.
+    pub fn new() -> Self {
+        BackoffPolicy {
+            initial_delay: Duration::from_millis(TELEGRAM_LONG_POLL_ERROR_DELAY_MILLISECONDS),
+            max_delay: Duration::from_secs(TELEGRAM_LONG_POLL_MAX_ERROR_DELAY_SECONDS),
+            jitter: TELEGRAM_LONG_POLL_ERROR_DELAY_JITTER,
+        }
+    }
+
+    /// Set the delay after the first error.
+    ///
+    /// Defaults to 500 ms.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the maximum delay.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the largest fraction by which a delay is randomly shortened, between 0 and 1.
+    ///
+    /// Defaults to 0.2.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns the delay after `errors` consecutive errors.
+    pub(crate) fn delay(&self, errors: u32) -> Duration {
+        let exponent = errors.saturating_sub(1).min(31);
+        let delay = self
+            .initial_delay
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if self.jitter > 0.0 {
+            delay.mul_f64(1.0 - rand::thread_rng().gen_range(0.0..=self.jitter))
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// This type represents stream of Telegram API updates and uses
 /// long polling method under the hood.
@@ -29,10 +124,25 @@ pub struct UpdatesStream {
     timeout: Duration,
     allowed_updates: Vec<AllowedUpdate>,
     limit: Integer,
-    error_delay: Duration,
+    backoff: BackoffPolicy,
+    consecutive_errors: u32,
+    acks: Arc<Acks>,
+    manual_ack: bool,
+    shutdown_signal: Arc<ShutdownSignal>,
+    shutdown_requested: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    shutdown: Shutdown,
     next_poll_id: usize,
 }
 
+enum Shutdown {
+    Running,
+    /// Yielding the updates which were received before the shutdown.
+    Draining,
+    /// Confirming the offset with a last `GetUpdates` request.
+    Confirming(Pin<Box<dyn Future<Output = ()> + Send>>),
+    Finished,
+}
+
 impl Stream for UpdatesStream {
     type Item = Result<Update, Error>;
 
@@ -45,10 +155,24 @@ impl Stream for UpdatesStream {
 
         tracing::trace!("start stream polling");
 
+        if let Shutdown::Running = ref_mut.shutdown {
+            if ref_mut.poll_shutdown_requested(cx) {
+                tracing::debug!("shutdown requested, abandoning current request");
+                ref_mut.current_request = None;
+                ref_mut.shutdown = Shutdown::Draining;
+            }
+        }
+
         if let Some(value) = ref_mut.buffer.pop_front() {
             tracing::trace!(update = ?value, "returning buffered update");
+            if let Err(err) = ref_mut.acks.deliver(value.id, ref_mut.manual_ack) {
+                tracing::error!(error = %err, "unable to store update offset");
+            }
             return Poll::Ready(Some(Ok(value)));
         }
+        if !matches!(ref_mut.shutdown, Shutdown::Running) {
+            return ref_mut.poll_confirm_offset(cx);
+        }
         tracing::trace!("processing request");
 
         let result = match ref_mut.current_request {
@@ -75,6 +199,21 @@ impl Stream for UpdatesStream {
                     Poll::Ready(Ok(Some(updates))) => {
                         for update in updates {
                             tracing::trace!(update = ?update, "processing update");
+                            match update.kind {
+                                UpdateKind::Undecodable(ref undecodable) => tracing::warn!(
+                                    update_id = update.id,
+                                    error = %undecodable.error,
+                                    raw = %undecodable.raw,
+                                    "received undecodable update"
+                                ),
+                                UpdateKind::Unknown(ref raw) => tracing::debug!(
+                                    update_id = update.id,
+                                    raw = %raw,
+                                    "received update of unknown kind"
+                                ),
+                                _ => (),
+                            }
+                            ref_mut.api.learn_migration(&update);
                             ref_mut.last_update = max(update.id, ref_mut.last_update);
                             tracing::trace!(last_update = ref_mut.last_update);
                             ref_mut.buffer.push_back(update)
@@ -92,36 +231,21 @@ impl Stream for UpdatesStream {
 
         match result {
             Err(err) => {
-                let timeout = ref_mut.timeout + Duration::from_secs(1);
-                let mut get_updates = GetUpdates::new();
-                get_updates
-                    .offset(ref_mut.last_update + 1)
-                    .timeout(ref_mut.error_delay.as_secs() as Integer)
-                    .limit(ref_mut.limit)
-                    .allowed_updates(&ref_mut.allowed_updates);
-                tracing::trace!(request = ?get_updates, timeout=?timeout, "preparing new request");
-
-                let request = ref_mut.api.send_timeout(get_updates, timeout);
-                ref_mut.current_request = Some(Box::pin(request));
-                return Poll::Ready(Some(Err(err)));
+                ref_mut.consecutive_errors += 1;
+                let delay = ref_mut.backoff.delay(ref_mut.consecutive_errors);
+                tracing::debug!(delay = ?delay, errors = ref_mut.consecutive_errors, "backing off");
+                ref_mut.prepare_request(Some(delay));
+                Poll::Ready(Some(Err(err)))
             }
             Ok(false) => {
-                let timeout = ref_mut.timeout + Duration::from_secs(1);
-                let mut get_updates = GetUpdates::new();
-                get_updates
-                    .offset(ref_mut.last_update + 1)
-                    .timeout(ref_mut.error_delay.as_secs() as Integer)
-                    .limit(ref_mut.limit)
-                    .allowed_updates(&ref_mut.allowed_updates);
-                tracing::trace!(request = ?get_updates, timeout=?timeout, "preparing new request");
-
-                let request = ref_mut.api.send_timeout(get_updates, timeout);
-                ref_mut.current_request = Some(Box::pin(request));
+                ref_mut.consecutive_errors = 0;
+                ref_mut.prepare_request(None);
 
                 tracing::trace!("executing recursive call");
                 Pin::new(ref_mut).poll_next(cx)
             }
             Ok(true) => {
+                ref_mut.consecutive_errors = 0;
                 tracing::trace!("dropping request");
                 ref_mut.current_request = None;
                 tracing::trace!("executing recursive call");
@@ -142,11 +266,85 @@ impl UpdatesStream {
             timeout: Duration::from_secs(TELEGRAM_LONG_POLL_TIMEOUT_SECONDS),
             allowed_updates: Vec::new(),
             limit: TELEGRAM_LONG_POLL_LIMIT_MESSAGES,
-            error_delay: Duration::from_millis(TELEGRAM_LONG_POLL_ERROR_DELAY_MILLISECONDS),
+            backoff: BackoffPolicy::new(),
+            consecutive_errors: 0,
+            acks: Arc::new(Acks::default()),
+            manual_ack: false,
+            shutdown_signal: Arc::new(ShutdownSignal::default()),
+            shutdown_requested: None,
+            shutdown: Shutdown::Running,
             next_poll_id: 0,
         }
     }
 
+    fn poll_shutdown_requested(&mut self, cx: &mut Context) -> bool {
+        if self.shutdown_signal.is_requested() {
+            return true;
+        }
+        let signal = self.shutdown_signal.clone();
+        let requested = self
+            .shutdown_requested
+            .get_or_insert_with(|| Box::pin(signal.requested()));
+        requested.as_mut().poll(cx).is_ready()
+    }
+
+    fn poll_confirm_offset(&mut self, cx: &mut Context) -> Poll<Option<Result<Update, Error>>> {
+        if let Shutdown::Draining = self.shutdown {
+            // Telegram confirms all updates before the offset, updates returned
+            // by this request are delivered again after a restart.
+            let offset = self.acks.committed() + 1;
+            let mut get_updates = GetUpdates::new();
+            get_updates
+                .offset(offset)
+                .timeout(0)
+                .limit(1)
+                .allowed_updates(&self.allowed_updates);
+            tracing::debug!(offset = offset, "confirming update offset");
+
+            let request = self.api.send(get_updates);
+            let acks = self.acks.clone();
+            self.shutdown = Shutdown::Confirming(Box::pin(async move {
+                if let Err(err) = request.await {
+                    tracing::error!(error = %err, "unable to confirm update offset");
+                }
+                acks.flush().await;
+            }));
+        }
+
+        if let Shutdown::Confirming(ref mut request) = self.shutdown {
+            if request.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            tracing::debug!("stream is shut down");
+            self.shutdown = Shutdown::Finished;
+        }
+        Poll::Ready(None)
+    }
+
+    fn prepare_request(&mut self, delay: Option<Duration>) {
+        let timeout = self.timeout + Duration::from_secs(1);
+        let mut get_updates = GetUpdates::new();
+        get_updates
+            .offset(self.last_update + 1)
+            .timeout(self.timeout.as_secs() as Integer)
+            .limit(self.limit)
+            .allowed_updates(&self.allowed_updates);
+        tracing::trace!(request = ?get_updates, timeout=?timeout, "preparing new request");
+
+        let request = self.api.send_timeout(get_updates, timeout);
+        let acks = self.acks.clone();
+        let manual_ack = self.manual_ack;
+        self.current_request = Some(Box::pin(async move {
+            if let Some(delay) = delay {
+                sleep(delay).await;
+            }
+            if manual_ack {
+                acks.wait_all_acked().await;
+            }
+            request.await
+        }));
+    }
+
     /// Set timeout for long polling requests, this corresponds with `timeout` field
     /// in [getUpdates](https://core.telegram.org/bots/api#getupdates) method,
     /// also this stream sets an additional request timeout for `timeout + 1 second`
@@ -185,9 +383,343 @@ impl UpdatesStream {
     /// Set a delay between erroneous request and next request.
     /// This delay prevents busy looping in some cases.
     ///
-    /// Default delay is 500 ms.
+    /// This is the initial delay of the [backoff policy](#method.backoff),
+    /// default delay is 500 ms.
     pub fn error_delay(&mut self, delay: Duration) -> &mut Self {
-        self.error_delay = delay;
+        self.backoff = self.backoff.clone().initial_delay(delay);
         self
     }
+
+    /// Persist the id of the last handled update in `store`, and continue after
+    /// the stored update.
+    ///
+    /// Updates count as handled when they are yielded by the stream, unless
+    /// [`manual_ack`](#method.manual_ack) is enabled.
+    pub fn offset_store<S>(&mut self, store: S) -> Result<&mut Self, Error>
+    where
+        S: OffsetStore + 'static,
+    {
+        if let Some(offset) = self.acks.set_store(Box::new(store))? {
+            tracing::trace!(offset = offset, "loaded update offset");
+            self.last_update = max(self.last_update, offset);
+        }
+        Ok(self)
+    }
+
+    /// Only count updates as handled when they are acknowledged with
+    /// [`ack`](#method.ack) or an [`Acknowledger`](struct.Acknowledger.html),
+    /// which gives at-least-once processing across restarts.
+    ///
+    /// The next updates are only requested once all received updates are acknowledged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use telegram_bot::{Api, Error, FileOffsetStore};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn handle(api: Api) -> Result<(), Error> {
+    /// let mut stream = api.stream();
+    /// stream.offset_store(FileOffsetStore::new("offset"))?.manual_ack(true);
+    /// while let Some(update) = stream.next().await {
+    ///     let update = update?;
+    ///     // handle the update
+    ///     stream.ack(update.id)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn manual_ack(&mut self, manual_ack: bool) -> &mut Self {
+        self.manual_ack = manual_ack;
+        self
+    }
+
+    /// Confirm that the update with `update_id` was handled,
+    /// see [`manual_ack`](#method.manual_ack).
+    pub fn ack(&self, update_id: Integer) -> Result<(), Error> {
+        self.acks.ack(update_id)
+    }
+
+    /// Create a handle for acknowledging updates from other tasks,
+    /// see [`manual_ack`](#method.manual_ack).
+    pub fn acknowledger(&self) -> Acknowledger {
+        Acknowledger(self.acks.clone())
+    }
+
+    /// Create a handle for stopping the stream gracefully.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use telegram_bot::{Api, Error};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run(api: Api) -> Result<(), Error> {
+    /// let mut stream = api.stream();
+    /// let shutdown = stream.shutdown_handle();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_secs(60)).await;
+    ///     shutdown.shutdown();
+    /// });
+    ///
+    /// while let Some(update) = stream.next().await {
+    ///     // handle the update
+    /// }
+    /// api.drain(Duration::from_secs(10)).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle(self.shutdown_signal.clone())
+    }
+
+    /// Set the policy for delays between erroneous requests and next requests.
+    /// The delay grows exponentially with the number of consecutive errors.
+    pub fn backoff(&mut self, backoff: BackoffPolicy) -> &mut Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Convert into a stream of new incoming messages, other updates are skipped.
+    ///
+    /// See [`UpdateKindStream`](struct.UpdateKindStream.html) for the effect on
+    /// [`allowed_updates`](#method.allowed_updates).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use telegram_bot::{Api, Error, MessageKind};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run(api: Api) -> Result<(), Error> {
+    /// let mut messages = api.stream().messages();
+    /// while let Some(message) = messages.next().await {
+    ///     if let MessageKind::Text { ref data, .. } = message?.kind {
+    ///         println!("{}", data);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn messages(self) -> UpdateKindStream<Message> {
+        self.kind_stream(AllowedUpdate::Message, |kind| match kind {
+            UpdateKind::Message(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Convert into a stream of edited messages, other updates are skipped.
+    pub fn edited_messages(self) -> UpdateKindStream<Message> {
+        self.kind_stream(AllowedUpdate::EditedMessage, |kind| match kind {
+            UpdateKind::EditedMessage(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Convert into a stream of callback queries, other updates are skipped.
+    pub fn callback_queries(self) -> UpdateKindStream<CallbackQuery> {
+        self.kind_stream(AllowedUpdate::CallbackQuery, |kind| match kind {
+            UpdateKind::CallbackQuery(query) => Some(query),
+            _ => None,
+        })
+    }
+
+    /// Convert into a stream of inline queries, other updates are skipped.
+    pub fn inline_queries(self) -> UpdateKindStream<InlineQuery> {
+        self.kind_stream(AllowedUpdate::InlineQuery, |kind| match kind {
+            UpdateKind::InlineQuery(query) => Some(query),
+            _ => None,
+        })
+    }
+
+    fn kind_stream<T>(
+        mut self,
+        allowed: AllowedUpdate,
+        extract: fn(UpdateKind) -> Option<T>,
+    ) -> UpdateKindStream<T> {
+        if self.allowed_updates.is_empty() {
+            self.allowed_updates = vec![allowed];
+        } else if !self.allowed_updates.contains(&allowed) {
+            self.allowed_updates.push(allowed);
+        }
+        // Payloads don't carry the update id, so they can't be acknowledged.
+        self.manual_ack = false;
+        UpdateKindStream {
+            stream: self,
+            extract,
+        }
+    }
+}
+
+/// Stream of the payloads of one kind of updates, created by
+/// [`UpdatesStream::messages`](struct.UpdatesStream.html#method.messages) and similar methods.
+///
+/// Unless [`allowed_updates`](struct.UpdatesStream.html#method.allowed_updates) were set,
+/// only updates of this kind are requested from Telegram. Otherwise this kind is added
+/// to the allowed updates. Updates count as handled when they are received,
+/// [`manual_ack`](struct.UpdatesStream.html#method.manual_ack) is disabled.
+#[must_use = "streams do nothing unless polled"]
+pub struct UpdateKindStream<T> {
+    stream: UpdatesStream,
+    extract: fn(UpdateKind) -> Option<T>,
+}
+
+impl<T> UpdateKindStream<T> {
+    /// Create a handle for stopping the stream gracefully, see
+    /// [`UpdatesStream::shutdown_handle`](struct.UpdatesStream.html#method.shutdown_handle).
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.stream.shutdown_handle()
+    }
+}
+
+impl<T> Stream for UpdateKindStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let ref_mut = self.get_mut();
+        loop {
+            match ready!(Pin::new(&mut ref_mut.stream).poll_next(cx)) {
+                Some(Ok(update)) => match (ref_mut.extract)(update.kind) {
+                    Some(item) => return Poll::Ready(Some(Ok(item))),
+                    None => tracing::trace!(update_id = update.id, "skipping update"),
+                },
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for UpdateKindStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateKindStream")
+            .field("allowed_updates", &self.stream.allowed_updates)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use telegram_bot_raw::{AllowedUpdate, UpdateKind};
+    use tokio::time::Instant;
+
+    use super::BackoffPolicy;
+    use crate::connector::MockConnector;
+    use crate::Api;
+
+    const MIGRATE_TO_CHAT_ID: &str =
+        include_str!("../../raw/tests/update_assets/migrate_to_chat_id.json");
+    const INLINE_QUERY: &str = include_str!("../../raw/tests/update_assets/inline_query.json");
+    const UNDECODABLE_UPDATE: &str =
+        include_str!("../../raw/tests/update_assets/undecodable_update.json");
+    const BAD_GATEWAY: &str = r#"{"ok":false,"error_code":502,"description":"Bad Gateway"}"#;
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = BackoffPolicy::new()
+            .max_delay(Duration::from_secs(3))
+            .jitter(0.0);
+        let delays: Vec<_> = (1..6).map(|errors| backoff.delay(errors)).collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_millis(500),
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(3),
+                Duration::from_secs(3),
+            ]
+        );
+
+        let backoff = BackoffPolicy::new().jitter(0.5);
+        for _ in 0..100 {
+            let delay = backoff.delay(2);
+            assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_error_backoff() {
+        let mock = MockConnector::new();
+        mock.respond_once("getUpdates", BAD_GATEWAY)
+            .respond_once("getUpdates", BAD_GATEWAY)
+            .respond_once(
+                "getUpdates",
+                format!(r#"{{"ok":true,"result":[{}]}}"#, MIGRATE_TO_CHAT_ID),
+            );
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+        let mut stream = api.stream();
+        stream
+            .timeout(Duration::from_secs(10))
+            .backoff(BackoffPolicy::new().jitter(0.0));
+
+        let start = Instant::now();
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(start.elapsed(), Duration::from_secs(0));
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.id, 424151279);
+        assert_eq!(start.elapsed(), Duration::from_millis(1500));
+
+        let bodies = mock.request_bodies("getUpdates");
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|body| body["timeout"] == 10));
+    }
+
+    #[tokio::test]
+    async fn test_kind_streams() {
+        let mock = MockConnector::new();
+        mock.respond_once(
+            "getUpdates",
+            format!(
+                r#"{{"ok":true,"result":[{},{}]}}"#,
+                MIGRATE_TO_CHAT_ID, INLINE_QUERY
+            ),
+        );
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+        let mut queries = api.stream().inline_queries();
+        let query = queries.next().await.unwrap().unwrap();
+        assert_eq!(query.query, "user query");
+        assert_eq!(
+            mock.request_bodies("getUpdates")[0]["allowed_updates"],
+            serde_json::json!(["inline_query"])
+        );
+
+        let mut stream = api.stream();
+        stream.allowed_updates(&[AllowedUpdate::CallbackQuery]);
+        let messages = stream.messages();
+        assert_eq!(
+            messages.stream.allowed_updates,
+            vec![AllowedUpdate::CallbackQuery, AllowedUpdate::Message]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_update() {
+        let mock = MockConnector::new();
+        mock.respond_once(
+            "getUpdates",
+            format!(
+                r#"{{"ok":true,"result":[{},{}]}}"#,
+                UNDECODABLE_UPDATE, INLINE_QUERY
+            ),
+        )
+        .respond_once("getUpdates", r#"{"ok":true,"result":[]}"#);
+        let api = Api::with_connector("token", Box::new(mock.clone()));
+        let mut stream = api.stream();
+
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.id, 424151281);
+        assert!(matches!(update.kind, UpdateKind::Undecodable(_)));
+        let update = stream.next().await.unwrap().unwrap();
+        assert!(matches!(update.kind, UpdateKind::InlineQuery(_)));
+
+        let _ = tokio::time::timeout(Duration::from_millis(10), stream.next()).await;
+        assert_eq!(mock.request_bodies("getUpdates")[1]["offset"], 424151282);
+    }
 }
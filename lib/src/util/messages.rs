@@ -4,8 +4,10 @@
 
 use crate::prelude::CanGetFile;
 use crate::types::{
-    requests::get_file::GetFile, ChannelPost, Message, MessageKind, MessageOrChannelPost,
+    requests::get_file::GetFile, ChannelPost, Message, MessageEntity, MessageEntityKind,
+    MessageKind, MessageOrChannelPost, ParseMode,
 };
+use crate::util::rich_text::RichText;
 
 /// A trait to obtain text from a message.
 ///
@@ -35,7 +37,8 @@ impl MessageText for MessageKind {
         match self {
             MessageKind::Text { data, .. } => Some(data.to_owned()),
             MessageKind::Audio { data } => data.title.to_owned(),
-            MessageKind::Document { data, caption } => {
+            MessageKind::Animation { caption, .. } => caption.to_owned(),
+            MessageKind::Document { data, caption, .. } => {
                 caption.clone().or_else(|| data.file_name.clone())
             }
             MessageKind::Photo { caption, .. } => caption.to_owned(),
@@ -47,6 +50,16 @@ impl MessageText for MessageKind {
             MessageKind::Location { .. } => None,
             MessageKind::Poll { data } => Some(data.question.to_owned()),
             MessageKind::Venue { data } => Some(data.title.to_owned()),
+            MessageKind::Dice { data } => Some(data.emoji.to_owned()),
+            MessageKind::Game { data } => Some(data.title.to_owned()),
+            MessageKind::Invoice { data } => Some(data.title.to_owned()),
+            MessageKind::SuccessfulPayment { .. } => None,
+            MessageKind::ConnectedWebsite { .. } => None,
+            MessageKind::ProximityAlertTriggered { .. } => None,
+            MessageKind::MessageAutoDeleteTimerChanged { .. } => None,
+            MessageKind::VideoChatStarted { .. } => None,
+            MessageKind::VideoChatEnded { .. } => None,
+            MessageKind::VideoChatParticipantsInvited { .. } => None,
             MessageKind::NewChatMembers { .. } => None,
             MessageKind::LeftChatMember { .. } => None,
             MessageKind::NewChatTitle { data } => Some(data.to_owned()),
@@ -69,6 +82,214 @@ impl MessageText for ChannelPost {
     }
 }
 
+/// A trait to obtain the formatted text of a message, e.g. for quoting it in another chat.
+///
+/// The text or the caption is rebuilt from its entities, entities which overlap
+/// are split so that they nest.
+pub trait MessageRichText {
+    /// Obtain formatted text from a message if available.
+    fn to_rich_text(&self) -> Option<RichText>;
+
+    /// Obtain text from a message as HTML if available.
+    fn to_html(&self) -> Option<(String, ParseMode)> {
+        self.to_rich_text().map(|text| text.html())
+    }
+
+    /// Obtain text from a message as MarkdownV2 if available.
+    fn to_markdown_v2(&self) -> Option<(String, ParseMode)> {
+        self.to_rich_text().map(|text| text.markdown_v2())
+    }
+}
+
+impl MessageRichText for MessageOrChannelPost {
+    fn to_rich_text(&self) -> Option<RichText> {
+        match self {
+            MessageOrChannelPost::Message(msg) => msg.to_rich_text(),
+            MessageOrChannelPost::ChannelPost(post) => post.to_rich_text(),
+        }
+    }
+}
+
+impl MessageRichText for Message {
+    fn to_rich_text(&self) -> Option<RichText> {
+        self.kind.to_rich_text()
+    }
+}
+
+impl MessageRichText for MessageKind {
+    fn to_rich_text(&self) -> Option<RichText> {
+        self.text_with_entities()
+            .map(|(text, entities)| rich_text_from_entities(text, entities))
+    }
+}
+
+impl MessageRichText for ChannelPost {
+    fn to_rich_text(&self) -> Option<RichText> {
+        self.kind.to_rich_text()
+    }
+}
+
+/// Entity with its position as byte range in the text.
+struct Span<'a> {
+    start: usize,
+    end: usize,
+    kind: &'a MessageEntityKind,
+}
+
+impl<'a> Span<'a> {
+    /// Code, preformatted blocks and custom emoji can't contain other entities.
+    fn is_atomic(&self) -> bool {
+        matches!(
+            self.kind,
+            MessageEntityKind::Code | MessageEntityKind::Pre(_) | MessageEntityKind::CustomEmoji(_)
+        )
+    }
+}
+
+/// Byte positions in `text` by position in UTF-16 code units,
+/// `None` inside of surrogate pairs.
+fn byte_positions(text: &str) -> Vec<Option<usize>> {
+    let mut positions = Vec::with_capacity(text.len() + 1);
+    for (index, c) in text.char_indices() {
+        positions.push(Some(index));
+        if c.len_utf16() == 2 {
+            positions.push(None);
+        }
+    }
+    positions.push(Some(text.len()));
+    positions
+}
+
+fn rich_text_from_entities(text: &str, entities: &[MessageEntity]) -> RichText {
+    let positions = byte_positions(text);
+    let position = |offset: i64| -> Option<usize> {
+        if offset < 0 {
+            return None;
+        }
+        positions.get(offset as usize).cloned().flatten()
+    };
+
+    // Entities which clients detect on their own, such as mentions, are kept as plain text.
+    let mut spans: Vec<Span> = entities
+        .iter()
+        .filter(|entity| {
+            matches!(
+                entity.kind,
+                MessageEntityKind::Bold
+                    | MessageEntityKind::Italic
+                    | MessageEntityKind::Underline
+                    | MessageEntityKind::Strikethrough
+                    | MessageEntityKind::Spoiler
+                    | MessageEntityKind::Blockquote
+                    | MessageEntityKind::Code
+                    | MessageEntityKind::Pre(_)
+                    | MessageEntityKind::TextLink(_)
+                    | MessageEntityKind::TextMention(_)
+                    | MessageEntityKind::CustomEmoji(_)
+            )
+        })
+        .filter_map(|entity| {
+            let start = position(entity.offset)?;
+            let end = position(entity.offset.checked_add(entity.length)?)?;
+            if start < end {
+                Some(Span {
+                    start,
+                    end,
+                    kind: &entity.kind,
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+    // Outer entities first, so that an entity only nests in entities before it.
+    spans.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut bounds: Vec<usize> = spans
+        .iter()
+        .flat_map(|span| vec![span.start, span.end])
+        .chain(vec![0, text.len()])
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+
+    // Open entities with the text inside of them so far.
+    let mut stack: Vec<(usize, RichText, String)> = Vec::new();
+    let mut root = RichText::new();
+
+    for segment in bounds.windows(2) {
+        let (from, to) = (segment[0], segment[1]);
+        let mut active: Vec<usize> = Vec::new();
+        for (index, span) in spans.iter().enumerate() {
+            if span.start <= from && to <= span.end {
+                active.push(index);
+                if span.is_atomic() {
+                    break;
+                }
+            }
+        }
+
+        // Close entities which end here or overlap with a later one, and reopen the rest.
+        let common = stack
+            .iter()
+            .zip(&active)
+            .take_while(|((open, _, _), active)| open == *active)
+            .count();
+        while stack.len() > common {
+            close(&mut stack, &mut root, &spans);
+        }
+        for &index in &active[common..] {
+            stack.push((index, RichText::new(), String::new()));
+        }
+
+        let part = &text[from..to];
+        match stack.last_mut() {
+            Some((_, text, plain)) => {
+                *text = std::mem::take(text).push(part);
+                plain.push_str(part);
+            }
+            None => root = root.push(part),
+        }
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut root, &spans);
+    }
+    root
+}
+
+/// Close the innermost open entity and add it to its parent.
+fn close(stack: &mut Vec<(usize, RichText, String)>, root: &mut RichText, spans: &[Span]) {
+    let (index, text, plain) = match stack.pop() {
+        Some(open) => open,
+        None => return,
+    };
+    let parent = match stack.last_mut() {
+        Some((_, parent, parent_plain)) => {
+            parent_plain.push_str(&plain);
+            parent
+        }
+        None => root,
+    };
+    let wrapped = std::mem::take(parent);
+    *parent = match spans[index].kind {
+        MessageEntityKind::Bold => wrapped.bold(text),
+        MessageEntityKind::Italic => wrapped.italic(text),
+        MessageEntityKind::Underline => wrapped.underline(text),
+        MessageEntityKind::Strikethrough => wrapped.strikethrough(text),
+        MessageEntityKind::Spoiler => wrapped.spoiler(text),
+        MessageEntityKind::Blockquote => wrapped.blockquote(text),
+        MessageEntityKind::Code => wrapped.code(plain),
+        MessageEntityKind::Pre(None) => wrapped.pre(plain),
+        MessageEntityKind::Pre(Some(ref language)) => {
+            wrapped.pre_with_language(plain, language.as_str())
+        }
+        MessageEntityKind::TextLink(ref url) => wrapped.link(text, url.as_str()),
+        MessageEntityKind::TextMention(ref user) => wrapped.mention(text, user),
+        MessageEntityKind::CustomEmoji(ref id) => wrapped.custom_emoji(plain, id.as_str()),
+        _ => wrapped.push(text),
+    };
+}
+
 /// A trait to obtain `GetFile` requests from a message.
 ///
 /// Many message kinds such as `Sticker` return a single `GetFile`.
@@ -99,6 +320,13 @@ impl MessageGetFiles for MessageKind {
         match self {
             MessageKind::Text { .. } => None,
             MessageKind::Audio { data } => Some(vec![data.get_file()]),
+            MessageKind::Animation { data, .. } => {
+                let mut files = vec![data.get_file()];
+                if let Some(thumb) = &data.thumb {
+                    files.push(thumb.get_file());
+                }
+                Some(files)
+            }
             MessageKind::Document { data, .. } => {
                 let mut files = vec![data.get_file()];
                 if let Some(thumb) = &data.thumb {
@@ -129,6 +357,16 @@ impl MessageGetFiles for MessageKind {
             MessageKind::Location { .. } => None,
             MessageKind::Poll { .. } => None,
             MessageKind::Venue { .. } => None,
+            MessageKind::Dice { .. } => None,
+            MessageKind::Game { .. } => None,
+            MessageKind::Invoice { .. } => None,
+            MessageKind::SuccessfulPayment { .. } => None,
+            MessageKind::ConnectedWebsite { .. } => None,
+            MessageKind::ProximityAlertTriggered { .. } => None,
+            MessageKind::MessageAutoDeleteTimerChanged { .. } => None,
+            MessageKind::VideoChatStarted { .. } => None,
+            MessageKind::VideoChatEnded { .. } => None,
+            MessageKind::VideoChatParticipantsInvited { .. } => None,
             MessageKind::NewChatMembers { .. } => None,
             MessageKind::LeftChatMember { .. } => None,
             MessageKind::NewChatTitle { .. } => None,
@@ -152,3 +390,74 @@ impl MessageGetFiles for ChannelPost {
         self.kind.get_files()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MessageRichText;
+    use crate::types::{Message, ParseMode};
+
+    fn parse_message(fields: &str) -> Message {
+        let json = format!(
+            r#"{{"message_id":1,"date":0,"chat":{{"id":1,"type":"private","first_name":"user"}},"from":{{"id":1,"is_bot":false,"first_name":"user"}},{}}}"#,
+            fields
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_overlapping_entities() {
+        // The underline splits the emoji and is skipped, the mention is kept as plain text.
+        let message = parse_message(
+            r#""text":"x 👋 1*2 <b> end","entities":[
+                {"type":"bold","offset":0,"length":8},
+                {"type":"italic","offset":5,"length":7},
+                {"type":"code","offset":9,"length":3},
+                {"type":"underline","offset":3,"length":1},
+                {"type":"mention","offset":13,"length":3},
+                {"type":"text_link","offset":13,"length":3,"url":"https://t.me/a_b"}
+            ]"#,
+        );
+
+        assert_eq!(
+            message.to_html(),
+            Some((
+                "<b>x 👋 <i>1*2</i></b><i> <code>&lt;b&gt;</code></i> \
+                 <a href=\"https://t.me/a_b\">end</a>"
+                    .to_string(),
+                ParseMode::Html
+            ))
+        );
+        assert_eq!(
+            message.to_markdown_v2(),
+            Some((
+                "*x 👋 _1\\*2_*_ `<b>`_ [end](https://t.me/a_b)".to_string(),
+                ParseMode::MarkdownV2
+            ))
+        );
+    }
+
+    #[test]
+    fn test_caption_entities() {
+        let message = parse_message(
+            r#""photo":[{"file_id":"id","width":1,"height":1}],"caption":"> quote\nline 🙂",
+            "caption_entities":[
+                {"type":"blockquote","offset":0,"length":15},
+                {"type":"custom_emoji","offset":13,"length":2,"custom_emoji_id":"42"}
+            ]"#,
+        );
+
+        assert_eq!(
+            message.to_html().unwrap().0,
+            "<blockquote>&gt; quote\nline <tg-emoji emoji-id=\"42\">🙂</tg-emoji></blockquote>"
+        );
+        assert_eq!(
+            message.to_markdown_v2().unwrap().0,
+            ">\\> quote\n>line ![🙂](tg://emoji?id=42)"
+        );
+
+        let sticker = parse_message(
+            r#""sticker":{"file_id":"id","file_unique_id":"u","width":1,"height":1,"is_animated":false}"#,
+        );
+        assert_eq!(sticker.to_rich_text(), None);
+    }
+}
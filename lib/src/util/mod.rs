@@ -1,3 +1,4 @@
 //! Traits that extends raw types functionality
 
 pub mod messages;
+pub mod rich_text;
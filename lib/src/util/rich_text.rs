@@ -0,0 +1,586 @@
+//! Builder for formatted text which is escaped for the chosen markup.
+
+use crate::types::{
+    EditMessageText, Integer, MessageEntity, MessageEntityKind, ParseMode, SendMessage, ToChatRef,
+    ToMessageId, ToSourceChat, ToUserId,
+};
+
+/// Formatted text, which renders to MarkdownV2, HTML or entities.
+///
+/// Text added to the builder is escaped when rendering, so user input can be
+/// embedded without breaking the markup.
+///
+/// # Example
+///
+/// ```rust
+/// use telegram_bot::{ParseMode, RichText, UserId};
+///
+/// let text = RichText::new()
+///     .push("Hello, ")
+///     .mention("Fedor", UserId::new(1))
+///     .push("! ")
+///     .bold(RichText::from("Price: ").italic("1.5*2"));
+///
+/// assert_eq!(
+///     text.markdown_v2(),
+///     (
+///         "Hello, [Fedor](tg://user?id=1)\\! *Price: _1\\.5\\*2_*".to_string(),
+///         ParseMode::MarkdownV2
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Plain(String),
+    Styled(Style, RichText),
+    Code(String),
+    Pre(String, Option<String>),
+    CustomEmoji(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Style {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+    Blockquote,
+    Link(String),
+}
+
+impl RichText {
+    /// Create an empty `RichText`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append plain text or other rich text.
+    pub fn push<T: Into<RichText>>(mut self, text: T) -> Self {
+        self.nodes.extend(text.into().nodes);
+        self
+    }
+
+    /// Append bold text.
+    pub fn bold<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Bold, text)
+    }
+
+    /// Append italic text.
+    pub fn italic<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Italic, text)
+    }
+
+    /// Append underlined text.
+    pub fn underline<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Underline, text)
+    }
+
+    /// Append strikethrough text.
+    pub fn strikethrough<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Strikethrough, text)
+    }
+
+    /// Append text hidden as a spoiler.
+    pub fn spoiler<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Spoiler, text)
+    }
+
+    /// Append a quotation, which spans whole lines. In MarkdownV2 the quotation is put
+    /// on its own lines if it is surrounded by other text.
+    pub fn blockquote<T: Into<RichText>>(self, text: T) -> Self {
+        self.styled(Style::Blockquote, text)
+    }
+
+    /// Append text which links to `url`.
+    pub fn link<T: Into<RichText>, U: Into<String>>(self, text: T, url: U) -> Self {
+        self.styled(Style::Link(url.into()), text)
+    }
+
+    /// Append text which mentions `user`, even if the user has no username.
+    pub fn mention<T: Into<RichText>, U: ToUserId>(self, text: T, user: U) -> Self {
+        let url = format!("tg://user?id={}", user.to_user_id());
+        self.styled(Style::Link(url), text)
+    }
+
+    /// Append inline monowidth code.
+    pub fn code<T: Into<String>>(mut self, code: T) -> Self {
+        self.nodes.push(Node::Code(code.into()));
+        self
+    }
+
+    /// Append a monowidth code block.
+    pub fn pre<T: Into<String>>(mut self, code: T) -> Self {
+        self.nodes.push(Node::Pre(code.into(), None));
+        self
+    }
+
+    /// Append a monowidth code block highlighted as `language`.
+    pub fn pre_with_language<T: Into<String>, L: Into<String>>(
+        mut self,
+        code: T,
+        language: L,
+    ) -> Self {
+        self.nodes
+            .push(Node::Pre(code.into(), Some(language.into())));
+        self
+    }
+
+    /// Append the custom emoji with the identifier `id`, shown as `emoji` where
+    /// custom emoji aren't available.
+    pub fn custom_emoji<T: Into<String>, I: Into<String>>(mut self, emoji: T, id: I) -> Self {
+        self.nodes.push(Node::CustomEmoji(emoji.into(), id.into()));
+        self
+    }
+
+    /// Render the text as MarkdownV2.
+    pub fn markdown_v2(&self) -> (String, ParseMode) {
+        let mut writer = MarkdownV2Writer::default();
+        writer.write(self);
+        (writer.out, ParseMode::MarkdownV2)
+    }
+
+    /// Render the text as HTML.
+    pub fn html(&self) -> (String, ParseMode) {
+        let mut out = String::new();
+        write_html(&mut out, self);
+        (out, ParseMode::Html)
+    }
+
+    /// Render the text as plain text with entities, which needs no escaping at all.
+    pub fn entities(&self) -> (String, Vec<MessageEntity>) {
+        let mut writer = EntitiesWriter::default();
+        writer.write(self);
+        (writer.out, writer.entities)
+    }
+
+    fn styled<T: Into<RichText>>(mut self, style: Style, text: T) -> Self {
+        self.nodes.push(Node::Styled(style, text.into()));
+        self
+    }
+}
+
+impl<'a> From<&'a str> for RichText {
+    fn from(text: &'a str) -> Self {
+        RichText::from(text.to_string())
+    }
+}
+
+impl From<String> for RichText {
+    fn from(text: String) -> Self {
+        RichText {
+            nodes: vec![Node::Plain(text)],
+        }
+    }
+}
+
+fn escape(out: &mut String, text: &str, special: &str) {
+    for c in text.chars() {
+        if special.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+#[derive(Default)]
+struct MarkdownV2Writer {
+    out: String,
+    /// Whether the output ends with a `_` or `__` marker.
+    underscore: bool,
+    /// Whether the output is inside of a blockquote, whose lines start with `>`.
+    quote: bool,
+    /// Whether a blockquote just ended, so that following text starts on a new line.
+    quote_ended: bool,
+}
+
+impl MarkdownV2Writer {
+    fn write(&mut self, text: &RichText) {
+        for node in &text.nodes {
+            match node {
+                Node::Plain(text) => self.text(text, "_*[]()~`>#+-=|{}.!\\"),
+                Node::Styled(style, text) => {
+                    let (open, close) = match style {
+                        Style::Bold => ("*", "*"),
+                        Style::Italic => ("_", "_"),
+                        Style::Underline => ("__", "__"),
+                        Style::Strikethrough => ("~", "~"),
+                        Style::Spoiler => ("||", "||"),
+                        Style::Blockquote => (">", ""),
+                        Style::Link(_) => ("[", "]("),
+                    };
+                    let blockquote = *style == Style::Blockquote && !self.quote;
+                    if blockquote {
+                        // Blockquotes span whole lines.
+                        self.quote_ended = false;
+                        if !self.out.is_empty() && !self.out.ends_with('\n') {
+                            self.out.push('\n');
+                        }
+                    }
+                    self.marker(open);
+                    let quote = self.quote;
+                    self.quote = quote || blockquote;
+                    self.write(text);
+                    self.quote = quote;
+                    self.marker(close);
+                    self.quote_ended = self.quote_ended || blockquote;
+                    if let Style::Link(url) = style {
+                        self.text(url, ")\\");
+                        self.marker(")");
+                    }
+                }
+                Node::Code(code) => {
+                    self.marker("`");
+                    self.text(code, "`\\");
+                    self.marker("`");
+                }
+                Node::Pre(code, language) => {
+                    self.marker("```");
+                    if let Some(language) = language {
+                        self.text(language, "`\\");
+                    }
+                    self.newline();
+                    self.text(code, "`\\");
+                    self.newline();
+                    self.marker("```");
+                }
+                Node::CustomEmoji(emoji, id) => {
+                    self.marker("![");
+                    self.text(emoji, "_*[]()~`>#+-=|{}.!\\");
+                    self.marker("](tg://emoji?id=");
+                    self.text(id, ")\\");
+                    self.marker(")");
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str, special: &str) {
+        if text.is_empty() {
+            return;
+        }
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.newline();
+            }
+            if !line.is_empty() {
+                self.end_quote();
+            }
+            escape(&mut self.out, line, special);
+        }
+        self.underscore = false;
+    }
+
+    fn newline(&mut self) {
+        self.quote_ended = false;
+        self.out.push('\n');
+        if self.quote {
+            self.out.push('>');
+        }
+    }
+
+    fn end_quote(&mut self) {
+        if self.quote_ended {
+            self.quote_ended = false;
+            self.out.push('\n');
+        }
+    }
+
+    /// `___` is ambiguous between italic and underline, adjacent markers are separated by `\r`.
+    fn marker(&mut self, marker: &str) {
+        if !marker.is_empty() {
+            self.end_quote();
+        }
+        if self.underscore && marker.starts_with('_') {
+            self.out.push('\r');
+        }
+        self.out.push_str(marker);
+        self.underscore = marker.ends_with('_');
+    }
+}
+
+fn write_html(out: &mut String, text: &RichText) {
+    for node in &text.nodes {
+        match node {
+            Node::Plain(text) => escape_html(out, text),
+            Node::Styled(style, text) => {
+                let tag = match style {
+                    Style::Bold => "b",
+                    Style::Italic => "i",
+                    Style::Underline => "u",
+                    Style::Strikethrough => "s",
+                    Style::Spoiler => "tg-spoiler",
+                    Style::Blockquote => "blockquote",
+                    Style::Link(url) => {
+                        out.push_str("<a href=\"");
+                        escape_html(out, url);
+                        out.push_str("\">");
+                        write_html(out, text);
+                        out.push_str("</a>");
+                        continue;
+                    }
+                };
+                out.push_str(&format!("<{}>", tag));
+                write_html(out, text);
+                out.push_str(&format!("</{}>", tag));
+            }
+            Node::Code(code) => {
+                out.push_str("<code>");
+                escape_html(out, code);
+                out.push_str("</code>");
+            }
+            Node::Pre(code, language) => {
+                out.push_str("<pre>");
+                if let Some(language) = language {
+                    out.push_str("<code class=\"language-");
+                    escape_html(out, language);
+                    out.push_str("\">");
+                    escape_html(out, code);
+                    out.push_str("</code>");
+                } else {
+                    escape_html(out, code);
+                }
+                out.push_str("</pre>");
+            }
+            Node::CustomEmoji(emoji, id) => {
+                out.push_str("<tg-emoji emoji-id=\"");
+                escape_html(out, id);
+                out.push_str("\">");
+                escape_html(out, emoji);
+                out.push_str("</tg-emoji>");
+            }
+        }
+    }
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[derive(Default)]
+struct EntitiesWriter {
+    out: String,
+    entities: Vec<MessageEntity>,
+    /// Length of `out` in UTF-16 code units.
+    offset: Integer,
+}
+
+impl EntitiesWriter {
+    fn write(&mut self, text: &RichText) {
+        for node in &text.nodes {
+            match node {
+                Node::Plain(text) => self.text(text),
+                Node::Styled(style, text) => {
+                    let kind = match style {
+                        Style::Bold => MessageEntityKind::Bold,
+                        Style::Italic => MessageEntityKind::Italic,
+                        Style::Underline => MessageEntityKind::Underline,
+                        Style::Strikethrough => MessageEntityKind::Strikethrough,
+                        Style::Spoiler => MessageEntityKind::Spoiler,
+                        Style::Blockquote => MessageEntityKind::Blockquote,
+                        Style::Link(url) => MessageEntityKind::TextLink(url.clone()),
+                    };
+                    // Push the entity before the nested ones, so entities stay sorted by offset.
+                    let index = self.entities.len();
+                    let offset = self.offset;
+                    self.entities.push(MessageEntity {
+                        offset,
+                        length: 0,
+                        kind,
+                    });
+                    self.write(text);
+                    if self.offset == offset {
+                        self.entities.remove(index);
+                    } else {
+                        self.entities[index].length = self.offset - offset;
+                    }
+                }
+                Node::Code(code) => self.entity(code, MessageEntityKind::Code),
+                Node::Pre(code, language) => {
+                    self.entity(code, MessageEntityKind::Pre(language.clone()))
+                }
+                Node::CustomEmoji(emoji, id) => {
+                    self.entity(emoji, MessageEntityKind::CustomEmoji(id.clone()))
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.offset += text.encode_utf16().count() as Integer;
+    }
+
+    fn entity(&mut self, text: &str, kind: MessageEntityKind) {
+        let offset = self.offset;
+        self.text(text);
+        if self.offset > offset {
+            self.entities.push(MessageEntity {
+                offset,
+                length: self.offset - offset,
+                kind,
+            });
+        }
+    }
+}
+
+/// Send a message with rich text.
+pub trait CanSendRichText {
+    /// Create a request sending `text`, formatted with entities.
+    fn rich_text(&self, text: &RichText) -> SendMessage<'static>;
+}
+
+impl<C> CanSendRichText for C
+where
+    C: ToChatRef,
+{
+    fn rich_text(&self, text: &RichText) -> SendMessage<'static> {
+        let (text, entities) = text.entities();
+        let mut request = SendMessage::new(self, text);
+        request.entities(entities);
+        request
+    }
+}
+
+/// Replace the text of a message sent by the bot with rich text.
+pub trait CanEditRichText {
+    /// Create a request replacing the text with `text`, formatted with entities.
+    fn edit_rich_text(&self, text: &RichText) -> EditMessageText<'static>;
+}
+
+impl<M> CanEditRichText for M
+where
+    M: ToMessageId + ToSourceChat,
+{
+    fn edit_rich_text(&self, text: &RichText) -> EditMessageText<'static> {
+        let (text, entities) = text.entities();
+        let mut request = EditMessageText::new(self.to_source_chat(), self.to_message_id(), text);
+        request.entities(entities);
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanEditRichText, CanSendRichText, RichText};
+    use crate::types::{
+        EditMessageText, Message, MessageEntity, MessageEntityKind, MessageId, ParseMode,
+        SendMessage, UserId,
+    };
+
+    const MESSAGE: &str = r#"{"message_id":2,"date":0,"chat":{"id":1,"type":"private","first_name":"user"},"from":{"id":2,"is_bot":true,"first_name":"bot"},"text":"hi"}"#;
+
+    fn entity(offset: i64, length: i64, kind: MessageEntityKind) -> MessageEntity {
+        MessageEntity {
+            offset,
+            length,
+            kind,
+        }
+    }
+
+    fn sample() -> RichText {
+        RichText::new()
+            .push("1 < 2 & *3* ")
+            .bold(RichText::from("👍 ").italic("x_y"))
+            .push(" ")
+            .link("[docs]", "https://example.com/a_(b)?c=\"d\"")
+            .push(" ")
+            .code("a`b\\c")
+            .pre_with_language("fn main() {}\n", "rust")
+    }
+
+    #[test]
+    fn test_markdown_v2() {
+        let (text, parse_mode) = sample().markdown_v2();
+        assert_eq!(parse_mode, ParseMode::MarkdownV2);
+        assert_eq!(
+            text,
+            "1 < 2 & \\*3\\* *👍 _x\\_y_* [\\[docs\\]](https://example.com/a_(b\\)?c=\"d\") \
+             `a\\`b\\\\c````rust\nfn main() {}\n\n```"
+        );
+
+        let text = RichText::new().italic(RichText::new().underline("a"));
+        assert_eq!(text.markdown_v2().0, "_\r__a__\r_");
+    }
+
+    #[test]
+    fn test_markdown_v2_blockquote() {
+        let text = RichText::new().push("see ").blockquote("q\nr").push("end");
+        assert_eq!(text.markdown_v2().0, "see \n>q\n>r\nend");
+
+        let text = RichText::new().push("see\n").blockquote("q").push("\nend");
+        assert_eq!(text.markdown_v2().0, "see\n>q\nend");
+
+        let text = RichText::new().blockquote(RichText::from("q ").bold("b"));
+        assert_eq!(text.markdown_v2().0, ">q *b*");
+    }
+
+    #[test]
+    fn test_html() {
+        let (text, parse_mode) = sample().html();
+        assert_eq!(parse_mode, ParseMode::Html);
+        assert_eq!(
+            text,
+            "1 &lt; 2 &amp; *3* <b>👍 <i>x_y</i></b> \
+             <a href=\"https://example.com/a_(b)?c=&quot;d&quot;\">[docs]</a> \
+             <code>a`b\\c</code><pre><code class=\"language-rust\">fn main() {}\n</code></pre>"
+        );
+
+        let text = RichText::new().spoiler("s").mention("u", UserId::new(7));
+        assert_eq!(
+            text.html().0,
+            "<tg-spoiler>s</tg-spoiler><a href=\"tg://user?id=7\">u</a>"
+        );
+    }
+
+    #[test]
+    fn test_entities() {
+        let (text, entities) = sample().entities();
+        assert_eq!(text, "1 < 2 & *3* 👍 x_y [docs] a`b\\cfn main() {}\n");
+        assert_eq!(
+            entities,
+            vec![
+                entity(12, 6, MessageEntityKind::Bold),
+                entity(15, 3, MessageEntityKind::Italic),
+                entity(
+                    19,
+                    6,
+                    MessageEntityKind::TextLink("https://example.com/a_(b)?c=\"d\"".into())
+                ),
+                entity(26, 5, MessageEntityKind::Code),
+                entity(31, 13, MessageEntityKind::Pre(Some("rust".into()))),
+            ]
+        );
+
+        let (_, entities) = RichText::new().bold("").push("a").entities();
+        assert!(entities.is_empty());
+    }
+
+    #[test]
+    fn test_requests() {
+        let text = RichText::new().push("hi ").bold("there");
+        let entities = vec![entity(3, 5, MessageEntityKind::Bold)];
+
+        let mut expected = SendMessage::new(UserId::new(1), "hi there");
+        expected.entities(entities.clone());
+        assert_eq!(UserId::new(1).rich_text(&text), expected);
+
+        let message: Message = serde_json::from_str(MESSAGE).unwrap();
+        let mut expected = EditMessageText::new(UserId::new(1), MessageId::new(2), "hi there");
+        expected.entities(entities);
+        assert_eq!(message.edit_rich_text(&text), expected);
+    }
+}
@@ -0,0 +1,350 @@
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use hyper::body::HttpBody;
+use hyper::header::CONTENT_LENGTH;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use telegram_bot_raw::Update;
+
+use crate::errors::{Error, ErrorKind};
+
+const TELEGRAM_WEBHOOK_SECRET_TOKEN_HEADER: &str = "x-telegram-bot-api-secret-token";
+const TELEGRAM_WEBHOOK_BUFFER_UPDATES: usize = 100;
+const TELEGRAM_WEBHOOK_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// This type represents stream of Telegram API updates and uses
+/// webhooks under the hood.
+///
+/// The server listens on a local address and accepts updates POSTed by Telegram
+/// (usually through a reverse proxy). It yields the same items as [`UpdatesStream`],
+/// so update handlers work with either delivery mode. Register the public URL
+/// with the `SetWebhook` request.
+///
+/// The listener is bound immediately, but requests are only served once the stream
+/// is polled for the first time. Dropping the stream stops the server.
+///
+/// [`UpdatesStream`]: struct.UpdatesStream.html
+#[must_use = "streams do nothing unless polled"]
+pub struct WebhookServer {
+    listener: Option<TcpListener>,
+    local_addr: SocketAddr,
+    path: Option<String>,
+    secret_token: Option<String>,
+    buffer: usize,
+    max_body_size: usize,
+    receiver: Option<mpsc::Receiver<Result<Update, Error>>>,
+    server: Option<JoinHandle<()>>,
+}
+
+struct WebhookState {
+    path: Option<String>,
+    secret_token: Option<String>,
+    max_body_size: usize,
+    sender: mpsc::Sender<Result<Update, Error>>,
+}
+
+impl Stream for WebhookServer {
+    type Item = Result<Update, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let ref_mut = self.get_mut();
+
+        if ref_mut.receiver.is_none() {
+            let listener = match ref_mut.listener.take() {
+                Some(listener) => listener,
+                None => return Poll::Ready(None),
+            };
+            if let Err(err) = ref_mut.start(listener) {
+                tracing::error!(error = %err, "unable to start webhook server");
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+
+        match ref_mut.receiver {
+            Some(ref mut receiver) => receiver.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl WebhookServer {
+    /// Bind a new `WebhookServer` to the given local address.
+    ///
+    /// Use port `0` to let the operating system pick a free port,
+    /// see [`local_addr`](#method.local_addr).
+    pub fn bind(addr: SocketAddr) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).map_err(ErrorKind::from)?;
+        let local_addr = listener.local_addr().map_err(ErrorKind::from)?;
+        Ok(WebhookServer {
+            listener: Some(listener),
+            local_addr,
+            path: None,
+            secret_token: None,
+            buffer: TELEGRAM_WEBHOOK_BUFFER_UPDATES,
+            max_body_size: TELEGRAM_WEBHOOK_MAX_BODY_SIZE,
+            receiver: None,
+            server: None,
+        })
+    }
+
+    /// Address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Only accept updates POSTed to this path, e.g. `/telegram`.
+    /// Requests to other paths are answered with `404 Not Found`.
+    ///
+    /// By default updates are accepted on any path.
+    pub fn path<T: Into<String>>(&mut self, path: T) -> &mut Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Require the `X-Telegram-Bot-Api-Secret-Token` header to match this value,
+    /// this corresponds with `secret_token` field in
+    /// [setWebhook](https://core.telegram.org/bots/api#setwebhook) method.
+    /// Requests with a missing or wrong header are answered with `401 Unauthorized`.
+    pub fn secret_token<T: Into<String>>(&mut self, secret_token: T) -> &mut Self {
+        self.secret_token = Some(secret_token.into());
+        self
+    }
+
+    /// Set the number of received updates to keep until they are consumed from the stream.
+    /// When the buffer is full, Telegram's requests are held until there is room again.
+    ///
+    /// Defaults to 100.
+    pub fn buffer(&mut self, buffer: usize) -> &mut Self {
+        self.buffer = buffer.max(1);
+        self
+    }
+
+    /// Set the maximum size of a request body in bytes.
+    /// Larger requests are answered with `413 Payload Too Large`.
+    ///
+    /// Defaults to 1 MiB.
+    pub fn max_body_size(&mut self, max_body_size: usize) -> &mut Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    fn start(&mut self, listener: TcpListener) -> Result<(), Error> {
+        listener.set_nonblocking(true).map_err(ErrorKind::from)?;
+        let builder = Server::from_tcp(listener).map_err(ErrorKind::from)?;
+
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let state = Arc::new(WebhookState {
+            path: self.path.clone(),
+            secret_token: self.secret_token.clone(),
+            max_body_size: self.max_body_size,
+            sender: sender.clone(),
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(state.clone(), request)
+                }))
+            }
+        });
+
+        let server = builder.serve(make_service);
+        tracing::trace!(addr = %self.local_addr, "webhook server started");
+
+        self.receiver = Some(receiver);
+        self.server = Some(tokio::spawn(async move {
+            if let Err(err) = server.await {
+                tracing::error!(error = %err, "webhook server error");
+                let _ = sender.send(Err(ErrorKind::from(err).into())).await;
+            }
+        }));
+        Ok(())
+    }
+}
+
+impl Drop for WebhookServer {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.abort();
+        }
+    }
+}
+
+async fn handle_request(
+    state: Arc<WebhookState>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    if let Some(ref path) = state.path {
+        if request.uri().path() != path {
+            return Ok(empty_response(StatusCode::NOT_FOUND));
+        }
+    }
+
+    if let Some(ref secret_token) = state.secret_token {
+        let header = request
+            .headers()
+            .get(TELEGRAM_WEBHOOK_SECRET_TOKEN_HEADER)
+            .map(|value| value.as_bytes());
+        if header != Some(secret_token.as_bytes()) {
+            tracing::warn!("webhook request with invalid secret token");
+            return Ok(empty_response(StatusCode::UNAUTHORIZED));
+        }
+    }
+
+    let body = match read_body(request, state.max_body_size).await {
+        Ok(body) => body,
+        Err(status) => return Ok(empty_response(status)),
+    };
+
+    // Telegram retries a delivery until it is acknowledged, so an update that can't
+    // be decoded is still acknowledged and surfaced as an error in the stream.
+    let update = serde_json::from_slice::<Update>(&body).map_err(|err| ErrorKind::from(err).into());
+    tracing::trace!(update = ?update, "webhook update received");
+
+    match state.sender.send(update).await {
+        Ok(()) => Ok(empty_response(StatusCode::OK)),
+        Err(_) => Ok(empty_response(StatusCode::SERVICE_UNAVAILABLE)),
+    }
+}
+
+/// Read the request body, failing with `413 Payload Too Large` once it exceeds `max_size`.
+async fn read_body(request: Request<Body>, max_size: usize) -> Result<Vec<u8>, StatusCode> {
+    let content_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&length| length > max_size as u64);
+    if let Some(content_length) = content_length {
+        tracing::warn!(content_length, "webhook request too large");
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let mut body = request.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|err| {
+            tracing::error!(error = %err, "unable to read webhook request");
+            StatusCode::BAD_REQUEST
+        })?;
+        if bytes.len() + chunk.len() > max_size {
+            tracing::warn!("webhook request too large");
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+
+    use futures::StreamExt;
+    use hyper::{Body, Client, Request, StatusCode};
+
+    use telegram_bot_raw::{MessageKind, UpdateKind};
+
+    use super::WebhookServer;
+
+    const MIGRATE_TO_CHAT_ID: &str =
+        include_str!("../../raw/tests/update_assets/migrate_to_chat_id.json");
+
+    async fn post(addr: SocketAddr, secret_token: &str, body: &'static str) -> StatusCode {
+        let request = Request::post(format!("http://{}/telegram", addr))
+            .header("X-Telegram-Bot-Api-Secret-Token", secret_token)
+            .body(Body::from(body))
+            .unwrap();
+        Client::new().request(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_receive_update() {
+        let mut server = WebhookServer::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        server.path("/telegram").secret_token("secret");
+
+        let (status, update) = tokio::join!(
+            post(server.local_addr(), "secret", MIGRATE_TO_CHAT_ID),
+            async { server.next().await }
+        );
+
+        assert_eq!(status, StatusCode::OK);
+        let update = update.unwrap().unwrap();
+        assert_eq!(update.id, 424151279);
+        match update.kind {
+            UpdateKind::Message(message) => match message.kind {
+                MessageKind::MigrateToChatId { data } => assert_eq!(data, -1001113717682),
+                kind => panic!("unexpected message kind: {:?}", kind),
+            },
+            kind => panic!("unexpected update kind: {:?}", kind),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_secret_token() {
+        let mut server = WebhookServer::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        server.secret_token("secret");
+
+        let status = tokio::select! {
+            status = post(server.local_addr(), "wrong", MIGRATE_TO_CHAT_ID) => status,
+            update = server.next() => panic!("unexpected update: {:?}", update),
+        };
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_update() {
+        let mut server = WebhookServer::bind(([127, 0, 0, 1], 0).into()).unwrap();
+
+        let (status, update) =
+            tokio::join!(post(server.local_addr(), "", "{\"update_id\": 1"), async {
+                server.next().await
+            });
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(update.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_body_too_large() {
+        let mut server = WebhookServer::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        server.max_body_size(16);
+        let addr = server.local_addr();
+        tokio::spawn(async move { while server.next().await.is_some() {} });
+
+        // Rejected by `Content-Length`.
+        assert_eq!(
+            post(addr, "", MIGRATE_TO_CHAT_ID).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // Rejected while streaming a chunked body without `Content-Length`.
+        let chunks: Vec<Result<&'static str, Infallible>> = vec![Ok("{\"update_id\":"); 4];
+        let request = Request::post(format!("http://{}/", addr))
+            .body(Body::wrap_stream(futures::stream::iter(chunks)))
+            .unwrap();
+        assert!(request.headers().get("content-length").is_none());
+        let status = Client::new().request(request).await.unwrap().status();
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
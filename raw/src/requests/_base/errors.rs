@@ -10,6 +10,7 @@ pub struct Error(ErrorKind);
 pub(crate) enum ErrorKind {
     EmptyBody,
     TelegramError {
+        error_code: Option<Integer>,
         description: String,
         parameters: Option<ResponseParameters>,
     },
@@ -29,6 +30,52 @@ impl From<ErrorKind> for Error {
     }
 }
 
+impl Error {
+    /// Error code returned by Telegram, e.g. `400` or `403`.
+    pub fn error_code(&self) -> Option<Integer> {
+        match &self.0 {
+            ErrorKind::TelegramError { error_code, .. } => *error_code,
+            _ => None,
+        }
+    }
+
+    /// Human-readable description of the error returned by Telegram.
+    pub fn description(&self) -> Option<&str> {
+        match &self.0 {
+            ErrorKind::TelegramError { description, .. } => Some(description),
+            _ => None,
+        }
+    }
+
+    /// In case of exceeding flood control, the number of seconds left to wait
+    /// before the request can be repeated.
+    pub fn retry_after(&self) -> Option<Integer> {
+        self.parameters()
+            .and_then(|parameters| parameters.retry_after)
+    }
+
+    /// The group has been migrated to a supergroup with the specified identifier.
+    pub fn migrate_to_chat_id(&self) -> Option<Integer> {
+        self.parameters()
+            .and_then(|parameters| parameters.migrate_to_chat_id)
+    }
+
+    /// Returns `true` if the response could not be decoded.
+    pub fn is_json(&self) -> bool {
+        matches!(self.0, ErrorKind::Json(_))
+    }
+
+    fn parameters(&self) -> Option<&ResponseParameters> {
+        match &self.0 {
+            ErrorKind::TelegramError {
+                parameters: Some(parameters),
+                ..
+            } => Some(parameters),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.0 {
@@ -36,6 +83,7 @@ impl fmt::Display for Error {
             ErrorKind::TelegramError {
                 description,
                 parameters,
+                ..
             } => {
                 f.write_str(&description)?;
                 if let Some(parameters) = parameters {
@@ -1,6 +1,14 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::{Arc, Mutex};
 
 use bytes::Bytes;
+use futures_core::Stream;
 
 use crate::types::Text;
 use crate::url::telegram_api_url;
@@ -8,6 +16,8 @@ use crate::url::telegram_api_url;
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum RequestUrl {
     Method(&'static str),
+    /// Method which isn't known at compile time, see [`RawRequest`](../struct.RawRequest.html).
+    Custom(Cow<'static, str>),
 }
 
 impl RequestUrl {
@@ -15,11 +25,27 @@ impl RequestUrl {
         RequestUrl::Method(method)
     }
 
-    pub fn url(&self, token: &str) -> String {
+    pub fn custom<M: Into<Cow<'static, str>>>(method: M) -> Self {
+        RequestUrl::Custom(method.into())
+    }
+
+    /// Name of the method, e.g. `"sendMessage"`.
+    pub fn name(&self) -> &str {
         match self {
-            &RequestUrl::Method(method) => format!("{}bot{}/{}", telegram_api_url(), token, method),
+            RequestUrl::Method(method) => method,
+            RequestUrl::Custom(method) => method,
         }
     }
+
+    /// URL of the method on the Bot API server from the `TELEGRAM_API_URL` environment variable.
+    pub fn url(&self, token: &str) -> String {
+        self.url_with_base(&telegram_api_url(), token)
+    }
+
+    /// URL of the method on the Bot API server at `api_url`, e.g. `https://api.telegram.org/`.
+    pub fn url_with_base(&self, api_url: &str, token: &str) -> String {
+        format!("{}bot{}/{}", api_url, token, self.name())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -33,6 +59,86 @@ pub enum MultipartValue {
     Text(Text),
     Path { path: Text, file_name: Option<Text> },
     Data { file_name: Text, data: Bytes },
+    Stream { file_name: Text, stream: FileStream },
+}
+
+/// Stream of file contents which is read when the request is sent.
+pub type BoxFileStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// File contents which are streamed when the request is sent, see
+/// [`InputFileUpload::with_stream`](../types/struct.InputFileUpload.html#method.with_stream).
+///
+/// The stream can only be sent once, clones share it, so a request with a `FileStream`
+/// can't be retried.
+#[derive(Clone)]
+pub struct FileStream {
+    id: usize,
+    length: u64,
+    stream: Arc<Mutex<Option<BoxFileStream>>>,
+}
+
+impl FileStream {
+    /// Wrap `stream`, which must produce exactly `length` bytes.
+    pub fn new<S>(stream: S, length: u64) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        FileStream {
+            id: NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed),
+            length,
+            stream: Arc::new(Mutex::new(Some(Box::pin(stream)))),
+        }
+    }
+
+    /// Number of bytes the stream produces.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Take the stream out, returns `None` if it was already taken.
+    pub fn take(&self) -> Option<BoxFileStream> {
+        self.stream
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take()
+    }
+}
+
+impl fmt::Debug for FileStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileStream")
+            .field("id", &self.id)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+impl PartialEq for FileStream {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for FileStream {}
+
+impl PartialOrd for FileStream {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FileStream {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl Hash for FileStream {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
 }
 
 pub type Multipart = Vec<(&'static str, MultipartValue)>;
@@ -65,11 +171,23 @@ pub struct HttpRequest {
 }
 
 impl HttpRequest {
+    /// Name of the method, e.g. `"sendMessage"`.
+    ///
+    /// Returns an empty string for custom method names which were created at runtime,
+    /// see [`RawRequest`](../struct.RawRequest.html).
+    #[deprecated(note = "use `name_str`, which also returns custom method names")]
     pub fn name(&self) -> &'static str {
         match self.url {
             RequestUrl::Method(method) => method,
+            RequestUrl::Custom(Cow::Borrowed(method)) => method,
+            RequestUrl::Custom(Cow::Owned(_)) => "",
         }
     }
+
+    /// Name of the method, e.g. `"sendMessage"`.
+    pub fn name_str(&self) -> &str {
+        self.url.name()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -6,7 +6,7 @@ pub use self::errors::Error;
 pub(crate) use self::errors::ErrorKind;
 
 mod http;
-pub use self::http::{Body, Multipart, MultipartValue, RequestUrl};
+pub use self::http::{Body, BoxFileStream, FileStream, Multipart, MultipartValue, RequestUrl};
 pub use self::http::{HttpRequest, HttpResponse, Method};
 
 #[macro_use]
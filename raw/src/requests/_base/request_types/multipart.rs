@@ -63,6 +63,13 @@ macro_rules! multipart_field {
         }
     }};
 
+    ($self:expr, $result:expr, $field:ident($type:ident) => $val:expr,non_empty) => {{
+        let value = $val;
+        if !value.is_empty() {
+            multipart_field!($self, $result, $field ($type) => value);
+        }
+    }};
+
     ($self:expr, $result:expr, $field:ident($type:ident) => $val:expr,when_true) => {{
         let value = $val;
         multipart_field!($self, $result, $field ($type) => value, skip_if value);
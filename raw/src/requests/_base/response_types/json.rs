@@ -47,9 +47,11 @@ where
             match raw {
                 ResponseWrapper::Success { result } => Ok(<Self as JsonResponse>::map(result)),
                 ResponseWrapper::Error {
+                    error_code,
                     description,
                     parameters,
                 } => Err(ErrorKind::TelegramError {
+                    error_code,
                     description,
                     parameters,
                 }
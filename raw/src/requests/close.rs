@@ -0,0 +1,17 @@
+use crate::requests::*;
+
+/// Use this method to close the bot instance before moving it from one local server to another.
+/// Delete the webhook before calling this method. The method can't be called
+/// for 10 minutes after the bot is launched.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct Close;
+
+impl Request for Close {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("close"), self)
+    }
+}
@@ -10,6 +10,8 @@ pub struct EditMessageCaption<'s> {
     chat_id: ChatRef,
     message_id: MessageId,
     caption: Cow<'s, str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    caption_entities: Vec<MessageEntity>,
     #[serde(skip_serializing_if = "Option::is_none")]
     reply_markup: Option<ReplyMarkup>,
 }
@@ -34,10 +36,17 @@ impl<'s> EditMessageCaption<'s> {
             chat_id: chat.to_chat_ref(),
             message_id: message_id.to_message_id(),
             caption: caption.into(),
+            caption_entities: Vec::new(),
             reply_markup: None,
         }
     }
 
+    /// Format the caption with `entities`.
+    pub fn caption_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.caption_entities = entities;
+        self
+    }
+
     pub fn reply_markup<R>(&mut self, reply_markup: R) -> &mut Self
     where
         R: Into<ReplyMarkup>,
@@ -13,6 +13,8 @@ pub struct EditMessageText<'s> {
     text: Cow<'s, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     parse_mode: Option<ParseMode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    entities: Vec<MessageEntity>,
     #[serde(skip_serializing_if = "Not::not")]
     disable_web_page_preview: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -40,6 +42,7 @@ impl<'s> EditMessageText<'s> {
             message_id: message_id.to_message_id(),
             text: text.into(),
             parse_mode: None,
+            entities: Vec::new(),
             disable_web_page_preview: false,
             reply_markup: None,
         }
@@ -50,6 +53,12 @@ impl<'s> EditMessageText<'s> {
         self
     }
 
+    /// Format the text with `entities` instead of a markup set with `parse_mode`.
+    pub fn entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.entities = entities;
+        self
+    }
+
     pub fn disable_preview(&mut self) -> &mut Self {
         self.disable_web_page_preview = true;
         self
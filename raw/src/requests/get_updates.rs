@@ -54,7 +54,7 @@ impl GetUpdates {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum AllowedUpdate {
     #[serde(rename = "message")]
     Message,
@@ -74,4 +74,16 @@ pub enum AllowedUpdate {
     ShippingQuery,
     #[serde(rename = "pre_checkout_query")]
     PreCheckoutQuery,
+    #[serde(rename = "poll")]
+    Poll,
+    #[serde(rename = "poll_answer")]
+    PollAnswer,
+    #[serde(rename = "my_chat_member")]
+    MyChatMember,
+    #[serde(rename = "chat_member")]
+    ChatMember,
+    /// An update type which this crate doesn't know yet, as reported by `getWebhookInfo`.
+    /// It can't be requested.
+    #[serde(other, skip_serializing)]
+    Unknown,
 }
@@ -0,0 +1,17 @@
+use crate::requests::*;
+use crate::types::*;
+
+/// Use this method to get current webhook status. Requires no parameters.
+/// If the bot is using getUpdates, will return an object with the url field empty.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct GetWebhookInfo;
+
+impl Request for GetWebhookInfo {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonIdResponse<WebhookInfo>;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("getWebhookInfo"), self)
+    }
+}
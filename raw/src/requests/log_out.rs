@@ -0,0 +1,16 @@
+use crate::requests::*;
+
+/// Use this method to log out from the cloud Bot API server before launching the bot locally.
+/// After a successful call, the bot can't log in again using the same token for 10 minutes.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct LogOut;
+
+impl Request for LogOut {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("logOut"), self)
+    }
+}
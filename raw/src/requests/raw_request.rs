@@ -0,0 +1,98 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::requests::*;
+
+/// Request for an arbitrary Bot API method, for methods which this crate doesn't support yet.
+///
+/// The response is decoded into `T`, use `serde_json::Value` to get the raw result.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_json::json;
+/// use telegram_bot_raw::{Request, RawRequest};
+///
+/// let request: RawRequest<bool> = RawRequest::json(
+///     "setChatTitle",
+///     json!({"chat_id": -1001234567890i64, "title": "New title"}),
+/// );
+/// assert_eq!(request.serialize().unwrap().name_str(), "setChatTitle");
+/// ```
+#[must_use = "requests do nothing unless sent"]
+pub struct RawRequest<T> {
+    method: Cow<'static, str>,
+    body: RawRequestBody,
+    phantom: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RawRequestBody {
+    Json(serde_json::Value),
+    Multipart(Multipart),
+}
+
+impl<T> RawRequest<T> {
+    /// Call `method` with the parameters in `body`, which should be a JSON object.
+    pub fn json<M: Into<Cow<'static, str>>>(method: M, body: serde_json::Value) -> Self {
+        RawRequest {
+            method: method.into(),
+            body: RawRequestBody::Json(body),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Call `method` with a multipart body, e.g. to upload files.
+    ///
+    /// Field names of a [`Multipart`](type.Multipart.html) body are `&'static str`, so the fields
+    /// must be known at compile time. Use [`json`](#method.json) for parameters whose names are
+    /// only known at runtime.
+    pub fn multipart<M: Into<Cow<'static, str>>>(method: M, body: Multipart) -> Self {
+        RawRequest {
+            method: method.into(),
+            body: RawRequestBody::Multipart(body),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static> Request for RawRequest<T> {
+    type Type = DetachedRequestType;
+    type Response = JsonIdResponse<T>;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        let body = match self.body {
+            RawRequestBody::Json(ref body) => {
+                Body::Json(serde_json::to_string(body).map_err(ErrorKind::from)?)
+            }
+            RawRequestBody::Multipart(ref body) => Body::Multipart(body.clone()),
+        };
+        Ok(HttpRequest {
+            url: RequestUrl::custom(self.method.clone()),
+            method: Method::Post,
+            body,
+        })
+    }
+}
+
+impl<T> Clone for RawRequest<T> {
+    fn clone(&self) -> Self {
+        RawRequest {
+            method: self.method.clone(),
+            body: self.body.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for RawRequest<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RawRequest")
+            .field("method", &self.method)
+            .field("body", &self.body)
+            .finish()
+    }
+}
@@ -11,6 +11,7 @@ pub struct SendAudio<'c, 'p, 't> {
     audio: InputFile,
     caption: Option<Cow<'c, str>>,
     parse_mode: Option<ParseMode>,
+    caption_entities: Vec<MessageEntity>,
     duration: Option<Integer>,
     performer: Option<Cow<'p, str>>,
     title: Option<Cow<'t, str>>,
@@ -28,6 +29,7 @@ impl<'c, 'p, 't> ToMultipart for SendAudio<'c, 'p, 't> {
             (audio (raw));
             (caption (text), optional);
             (parse_mode (text), optional);
+            (caption_entities (json), non_empty);
             (duration (text), optional);
             (performer (text), optional);
             (title (text), optional);
@@ -59,6 +61,7 @@ impl<'c, 'p, 't> SendAudio<'c, 'p, 't> {
             audio: audio.into(),
             caption: None,
             parse_mode: None,
+            caption_entities: Vec::new(),
             duration: None,
             performer: None,
             title: None,
@@ -90,6 +93,12 @@ impl<'c, 'p, 't> SendAudio<'c, 'p, 't> {
         self
     }
 
+    /// Format the caption with `entities` instead of a markup set with `parse_mode`.
+    pub fn caption_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.caption_entities = entities;
+        self
+    }
+
     pub fn duration(&mut self, duration: Integer) -> &mut Self {
         self.duration = Some(duration);
         self
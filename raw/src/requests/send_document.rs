@@ -13,6 +13,7 @@ pub struct SendDocument<'c> {
     thumb: Option<InputFile>,
     caption: Option<Cow<'c, str>>,
     parse_mode: Option<ParseMode>,
+    caption_entities: Vec<MessageEntity>,
     reply_to_message_id: Option<MessageId>,
     disable_notification: bool,
     reply_markup: Option<ReplyMarkup>,
@@ -27,6 +28,7 @@ impl<'c> ToMultipart for SendDocument<'c> {
             (thumb (raw), optional);
             (caption (text), optional);
             (parse_mode (text), optional);
+            (caption_entities (json), non_empty);
             (reply_to_message_id (text), optional);
             (disable_notification (text), when_true);
             (reply_markup (json), optional);
@@ -55,6 +57,7 @@ impl<'c> SendDocument<'c> {
             thumb: None,
             caption: None,
             parse_mode: None,
+            caption_entities: Vec::new(),
             reply_to_message_id: None,
             reply_markup: None,
             disable_notification: false,
@@ -82,6 +85,12 @@ impl<'c> SendDocument<'c> {
         self
     }
 
+    /// Format the caption with `entities` instead of a markup set with `parse_mode`.
+    pub fn caption_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.caption_entities = entities;
+        self
+    }
+
     pub fn reply_to<R>(&mut self, to: R) -> &mut Self
     where
         R: ToMessageId,
@@ -12,6 +12,8 @@ pub struct SendMessage<'s> {
     text: Cow<'s, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     parse_mode: Option<ParseMode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    entities: Vec<MessageEntity>,
     #[serde(skip_serializing_if = "Not::not")]
     disable_web_page_preview: bool,
     #[serde(skip_serializing_if = "Not::not")]
@@ -41,6 +43,7 @@ impl<'s> SendMessage<'s> {
             chat_id: chat.to_chat_ref(),
             text: text.into(),
             parse_mode: None,
+            entities: Vec::new(),
             disable_web_page_preview: false,
             disable_notification: false,
             reply_to_message_id: None,
@@ -53,6 +56,12 @@ impl<'s> SendMessage<'s> {
         self
     }
 
+    /// Format the text with `entities` instead of a markup set with `parse_mode`.
+    pub fn entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.entities = entities;
+        self
+    }
+
     pub fn disable_preview(&mut self) -> &mut Self {
         self.disable_web_page_preview = true;
         self
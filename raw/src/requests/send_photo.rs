@@ -11,6 +11,7 @@ pub struct SendPhoto<'c> {
     photo: InputFile,
     caption: Option<Cow<'c, str>>,
     parse_mode: Option<ParseMode>,
+    caption_entities: Vec<MessageEntity>,
     reply_to_message_id: Option<MessageId>,
     disable_notification: bool,
     reply_markup: Option<ReplyMarkup>,
@@ -24,6 +25,7 @@ impl<'c> ToMultipart for SendPhoto<'c> {
             (photo (raw));
             (caption (text), optional);
             (parse_mode (text), optional);
+            (caption_entities (json), non_empty);
             (reply_to_message_id (text), optional);
             (disable_notification (text), when_true);
             (reply_markup (json), optional);
@@ -51,6 +53,7 @@ impl<'c> SendPhoto<'c> {
             photo: photo.into(),
             caption: None,
             parse_mode: None,
+            caption_entities: Vec::new(),
             reply_to_message_id: None,
             reply_markup: None,
             disable_notification: false,
@@ -70,6 +73,12 @@ impl<'c> SendPhoto<'c> {
         self
     }
 
+    /// Format the caption with `entities` instead of a markup set with `parse_mode`.
+    pub fn caption_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.caption_entities = entities;
+        self
+    }
+
     pub fn reply_to<R>(&mut self, to: R) -> &mut Self
     where
         R: ToMessageId,
@@ -26,6 +26,8 @@ pub struct SendPoll<'q, 'o, 'e> {
     explanation: Option<Cow<'e, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     explanation_parse_mode: Option<ParseMode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    explanation_entities: Vec<MessageEntity>,
     #[serde(skip_serializing_if = "Option::is_none")]
     open_period: Option<Integer>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,6 +73,7 @@ impl<'q, 'o, 'e> SendPoll<'q, 'o, 'e> {
             correct_option_id: None,
             explanation: None,
             explanation_parse_mode: None,
+            explanation_entities: Vec::new(),
             open_period: None,
             close_date: None,
             is_closed: false,
@@ -125,6 +128,12 @@ impl<'q, 'o, 'e> SendPoll<'q, 'o, 'e> {
         self
     }
 
+    /// Format the explanation with `entities` instead of a markup set with `parse_mode`.
+    pub fn explanation_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.explanation_entities = entities;
+        self
+    }
+
     pub fn open_period(&mut self, period: Integer) -> &mut Self {
         self.open_period = Some(period);
         self
@@ -11,6 +11,7 @@ pub struct SendVideo<'c> {
     video: InputFile,
     caption: Option<Cow<'c, str>>,
     parse_mode: Option<ParseMode>,
+    caption_entities: Vec<MessageEntity>,
     duration: Option<Integer>,
     width: Option<Integer>,
     height: Option<Integer>,
@@ -29,6 +30,7 @@ impl<'c> ToMultipart for SendVideo<'c> {
             (video (raw));
             (caption (text), optional);
             (parse_mode (text), optional);
+            (caption_entities (json), non_empty);
             (duration (text), optional);
             (width (text), optional);
             (height (text), optional);
@@ -61,6 +63,7 @@ impl<'c> SendVideo<'c> {
             video: video.into(),
             caption: None,
             parse_mode: None,
+            caption_entities: Vec::new(),
             duration: None,
             width: None,
             height: None,
@@ -93,6 +96,12 @@ impl<'c> SendVideo<'c> {
         self
     }
 
+    /// Format the caption with `entities` instead of a markup set with `parse_mode`.
+    pub fn caption_entities(&mut self, entities: Vec<MessageEntity>) -> &mut Self {
+        self.caption_entities = entities;
+        self
+    }
+
     pub fn duration(&mut self, duration: Integer) -> &mut Self {
         self.duration = Some(duration);
         self
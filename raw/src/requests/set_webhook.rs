@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+
+use crate::requests::*;
+use crate::types::*;
+
+/// Use this method to specify a url and receive incoming updates via an outgoing webhook.
+/// Whenever there is an update for the bot, Telegram will send an HTTPS POST request
+/// to the specified url, containing a JSON-serialized Update.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[must_use = "requests do nothing unless sent"]
+pub struct SetWebhook<'u, 'i, 's> {
+    url: Cow<'u, str>,
+    certificate: Option<InputFile>,
+    ip_address: Option<Cow<'i, str>>,
+    max_connections: Option<Integer>,
+    allowed_updates: Option<Vec<AllowedUpdate>>,
+    drop_pending_updates: bool,
+    secret_token: Option<Cow<'s, str>>,
+}
+
+impl<'u, 'i, 's> ToMultipart for SetWebhook<'u, 'i, 's> {
+    fn to_multipart(&self) -> Result<Multipart, Error> {
+        multipart_map! {
+            self,
+            (url (text));
+            (certificate (raw), optional);
+            (ip_address (text), optional);
+            (max_connections (text), optional);
+            (allowed_updates (json), optional);
+            (drop_pending_updates (text), when_true);
+            (secret_token (text), optional);
+        }
+    }
+}
+
+impl<'u, 'i, 's> Request for SetWebhook<'u, 'i, 's> {
+    type Type = MultipartRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("setWebhook"), self)
+    }
+}
+
+impl<'u, 'i, 's> SetWebhook<'u, 'i, 's> {
+    pub fn new<U>(url: U) -> Self
+    where
+        U: Into<Cow<'u, str>>,
+    {
+        SetWebhook {
+            url: url.into(),
+            certificate: None,
+            ip_address: None,
+            max_connections: None,
+            allowed_updates: None,
+            drop_pending_updates: false,
+            secret_token: None,
+        }
+    }
+
+    /// Upload your public key certificate so that the root certificate in use can be checked.
+    pub fn certificate<V>(&mut self, certificate: V) -> &mut Self
+    where
+        V: Into<InputFileUpload>,
+    {
+        self.certificate = Some(InputFile::from(certificate.into()));
+        self
+    }
+
+    /// The fixed IP address which will be used to send webhook requests
+    /// instead of the IP address resolved through DNS.
+    pub fn ip_address<T>(&mut self, ip_address: T) -> &mut Self
+    where
+        T: Into<Cow<'i, str>>,
+    {
+        self.ip_address = Some(ip_address.into());
+        self
+    }
+
+    /// Maximum allowed number of simultaneous HTTPS connections to the webhook
+    /// for update delivery, 1-100. Defaults to 40.
+    pub fn max_connections(&mut self, max_connections: Integer) -> &mut Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// List the types of updates you want your bot to receive.
+    /// Specify an empty list to receive all update types.
+    pub fn allowed_updates(&mut self, updates: &[AllowedUpdate]) -> &mut Self {
+        self.allowed_updates = Some(updates.to_vec());
+        self
+    }
+
+    /// Drop all pending updates.
+    pub fn drop_pending_updates(&mut self) -> &mut Self {
+        self.drop_pending_updates = true;
+        self
+    }
+
+    /// A secret token to be sent in a header `X-Telegram-Bot-Api-Secret-Token`
+    /// in every webhook request, 1-256 characters.
+    pub fn secret_token<T>(&mut self, secret_token: T) -> &mut Self
+    where
+        T: Into<Cow<'s, str>>,
+    {
+        self.secret_token = Some(secret_token.into());
+        self
+    }
+}
@@ -3,19 +3,22 @@ use serde::de::{Deserialize, Deserializer, Error};
 use crate::types::*;
 
 /// This object represents a Telegram user or bot.
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct User {
     /// Unique identifier for this user or bot.
     pub id: UserId,
     /// User‘s or bot’s first name.
     pub first_name: String,
     /// User‘s or bot’s last name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub last_name: Option<String>,
     /// User‘s or bot’s username.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
     /// True, if this user is a bot.
     pub is_bot: bool,
     /// IETF language tag of the user's language
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub language_code: Option<String>,
 }
 
@@ -70,8 +73,8 @@ pub enum MessageChat {
     Private(User),
     Group(Group),
     Supergroup(Supergroup),
-    #[doc(hidden)]
-    Unknown(RawChat),
+    /// A chat of a type which this crate doesn't know yet, as received.
+    Unknown(RawJson),
 }
 
 impl MessageChat {
@@ -80,7 +83,7 @@ impl MessageChat {
             MessageChat::Private(ref x) => x.id.into(),
             MessageChat::Group(ref x) => x.id.into(),
             MessageChat::Supergroup(ref x) => x.id.into(),
-            MessageChat::Unknown(ref x) => x.id.into(),
+            MessageChat::Unknown(ref x) => unknown_chat_id(x),
         }
     }
 }
@@ -92,8 +95,8 @@ pub enum Chat {
     Group(Group),
     Supergroup(Supergroup),
     Channel(Channel),
-    #[doc(hidden)]
-    Unknown(RawChat),
+    /// A chat of a type which this crate doesn't know yet, as received.
+    Unknown(RawJson),
 }
 
 impl Chat {
@@ -103,17 +106,23 @@ impl Chat {
             Chat::Group(ref x) => x.id.into(),
             Chat::Supergroup(ref x) => x.id.into(),
             Chat::Channel(ref x) => x.id.into(),
-            Chat::Unknown(ref x) => x.id.into(),
+            Chat::Unknown(ref x) => unknown_chat_id(x),
         }
     }
 }
 
+fn unknown_chat_id(raw: &RawJson) -> ChatId {
+    let id = raw.get("id").and_then(serde_json::Value::as_i64);
+    ChatId::new(id.unwrap_or_default())
+}
+
 impl<'de> Deserialize<'de> for Chat {
     fn deserialize<D>(deserializer: D) -> Result<Chat, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let raw: RawChat = Deserialize::deserialize(deserializer)?;
+        let json = serde_json::Value::deserialize(deserializer)?;
+        let raw: RawChat = serde_json::from_value(json.clone()).map_err(D::Error::custom)?;
 
         macro_rules! required_field {
             ($name:ident) => {{
@@ -151,7 +160,7 @@ impl<'de> Deserialize<'de> for Chat {
                 username: raw.username,
                 invite_link: raw.invite_link,
             }),
-            _ => Chat::Unknown(raw),
+            _ => Chat::Unknown(RawJson(json)),
         })
     }
 }
@@ -181,6 +181,9 @@ pub struct InlineQueryResultPhoto {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -215,6 +218,9 @@ pub struct InlineQueryResultGif {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -248,6 +254,9 @@ pub struct InlineQueryResultMpeg4Gif {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -274,6 +283,9 @@ pub struct InlineQueryResultVideo {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Video width
     #[serde(skip_serializing_if = "Option::is_none")]
     pub video_width: Option<Integer>,
@@ -307,6 +319,9 @@ pub struct InlineQueryResultAudio {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Performer
     #[serde(skip_serializing_if = "Option::is_none")]
     pub performer: Option<String>,
@@ -334,6 +349,9 @@ pub struct InlineQueryResultVoice {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Audio duration in seconds
     #[serde(skip_serializing_if = "Option::is_none")]
     pub audio_duration: Option<Integer>,
@@ -357,6 +375,9 @@ pub struct InlineQueryResultDocument {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// A valid URL for the file
     pub document_url: String,
     /// Mime type of the content of the file, either “application/pdf” or “application/zip”
@@ -505,6 +526,9 @@ pub struct InlineQueryResultCachedPhoto {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -528,6 +552,9 @@ pub struct InlineQueryResultCachedGif {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -551,6 +578,9 @@ pub struct InlineQueryResultCachedMpeg4Gif {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -590,6 +620,9 @@ pub struct InlineQueryResultCachedDocument {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -615,6 +648,9 @@ pub struct InlineQueryResultCachedVideo {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -637,6 +673,9 @@ pub struct InlineQueryResultCachedVoice {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -657,6 +696,9 @@ pub struct InlineQueryResultCachedAudio {
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the caption, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
     /// Inline keyboard attached to the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<InlineKeyboardMarkup>,
@@ -839,18 +881,53 @@ pub enum InputMessageContent {
     InputContactMessageContent(InputContactMessageContent),
 }
 
+/// Content of a text message, see [`InputTextMessageContent::new`](#method.new).
 #[derive(Serialize, Debug)]
+#[non_exhaustive]
 pub struct InputTextMessageContent {
     /// Text of the message to be sent, 1-4096 characters
     pub message_text: String,
     /// Send Markdown or HTML, if you want Telegram apps to show bold, italic, fixed-width text or inline URLs in the media caption.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_mode: Option<ParseMode>,
+    /// Special entities that appear in the message text, which can be specified instead of parse_mode
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<MessageEntity>,
     /// Disables link previews for links in the sent message
     #[serde(skip_serializing_if = "Not::not")]
     pub disable_web_page_preview: bool,
 }
 
+impl InputTextMessageContent {
+    /// Create the content of a message with the plain text `message_text`.
+    pub fn new<T: Into<String>>(message_text: T) -> Self {
+        InputTextMessageContent {
+            message_text: message_text.into(),
+            parse_mode: None,
+            entities: Vec::new(),
+            disable_web_page_preview: false,
+        }
+    }
+
+    /// Parse the text as markup.
+    pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    /// Format the text with `entities` instead of a markup set with `parse_mode`.
+    pub fn entities(mut self, entities: Vec<MessageEntity>) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Disable link previews for links in the message.
+    pub fn disable_preview(mut self) -> Self {
+        self.disable_web_page_preview = true;
+        self
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct InputLocationMessageContent {
     /// Latitude of the location in degrees
@@ -1,4 +1,7 @@
+use std::io;
+
 use bytes::Bytes;
+use futures_core::Stream;
 
 use crate::requests::*;
 use crate::types::*;
@@ -17,12 +20,14 @@ enum InputFileImpl {
     Ref(Text),
     Path { path: Text, file_name: Option<Text> },
     Data { file_name: Text, data: Bytes },
+    Stream { file_name: Text, stream: FileStream },
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 enum InputFileUploadImpl {
     Path { path: Text, file_name: Option<Text> },
     Data { file_name: Text, data: Bytes },
+    Stream { file_name: Text, stream: FileStream },
 }
 
 impl InputFileRef {
@@ -46,11 +51,25 @@ impl InputFileUpload {
         })
     }
 
+    /// Upload `length` bytes from `stream`, e.g. a file which is received from elsewhere.
+    ///
+    /// The stream is read when the request is sent, so the request can only be sent once.
+    pub fn with_stream<S>(stream: S, length: u64, file_name: impl Into<Text>) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        InputFileUpload(InputFileUploadImpl::Stream {
+            file_name: file_name.into(),
+            stream: FileStream::new(stream, length),
+        })
+    }
+
     pub fn file_name(&self, new_file_name: impl Into<Text>) -> Self {
         let mut this = self.clone();
         match &mut this.0 {
             InputFileUploadImpl::Path { file_name, .. } => *file_name = Some(new_file_name.into()),
             InputFileUploadImpl::Data { file_name, .. } => *file_name = new_file_name.into(),
+            InputFileUploadImpl::Stream { file_name, .. } => *file_name = new_file_name.into(),
         };
         this
     }
@@ -131,6 +150,9 @@ impl From<InputFileUpload> for InputFile {
             InputFileUploadImpl::Data { data, file_name } => {
                 InputFileImpl::Data { data, file_name }
             }
+            InputFileUploadImpl::Stream { file_name, stream } => {
+                InputFileImpl::Stream { file_name, stream }
+            }
         })
     }
 }
@@ -146,6 +168,10 @@ impl<'a> From<&'a InputFileUpload> for InputFile {
                 data: data.clone(),
                 file_name: file_name.clone(),
             },
+            InputFileUploadImpl::Stream { file_name, stream } => InputFileImpl::Stream {
+                file_name: file_name.clone(),
+                stream: stream.clone(),
+            },
         })
     }
 }
@@ -161,6 +187,10 @@ impl<'a> From<&'a mut InputFileUpload> for InputFile {
                 data: data.clone(),
                 file_name: file_name.clone(),
             },
+            InputFileUploadImpl::Stream { file_name, stream } => InputFileImpl::Stream {
+                file_name: file_name.clone(),
+                stream: stream.clone(),
+            },
         })
     }
 }
@@ -201,6 +231,10 @@ impl ToMultipartValue for InputFile {
                 file_name: file_name.clone(),
                 data: data.clone(),
             },
+            InputFileImpl::Stream { file_name, stream } => MultipartValue::Stream {
+                file_name: file_name.clone(),
+                stream: stream.clone(),
+            },
         }
     }
 }
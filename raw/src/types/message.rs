@@ -1,4 +1,5 @@
 use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, Serializer};
 
 use crate::types::*;
 use crate::url::*;
@@ -96,12 +97,23 @@ pub enum MessageKind {
         /// Information about the file.
         data: Audio,
     },
+    /// Message is an animation, e.g. a GIF or a H.264/MPEG-4 AVC video without sound.
+    Animation {
+        /// Information about the animation.
+        data: Animation,
+        /// Caption for the animation, 0-1024 characters.
+        caption: Option<String>,
+        /// Special entities like usernames, URLs, bot commands, etc. that appear in the caption.
+        caption_entities: Vec<MessageEntity>,
+    },
     /// Message is a general file.
     Document {
         /// Information about the file.
         data: Document,
         /// Caption for the document, 0-200 characters.
         caption: Option<String>,
+        /// Special entities like usernames, URLs, bot commands, etc. that appear in the caption.
+        caption_entities: Vec<MessageEntity>,
     },
     /// Message is a photo.
     Photo {
@@ -109,6 +121,8 @@ pub enum MessageKind {
         data: Vec<PhotoSize>,
         /// Caption for the photo, 0-200 characters.
         caption: Option<String>,
+        /// Special entities like usernames, URLs, bot commands, etc. that appear in the caption.
+        caption_entities: Vec<MessageEntity>,
         /// The unique identifier of a media message group this message belongs to.
         media_group_id: Option<String>,
     },
@@ -123,6 +137,8 @@ pub enum MessageKind {
         data: Video,
         /// Caption for the video, 0-200 characters.
         caption: Option<String>,
+        /// Special entities like usernames, URLs, bot commands, etc. that appear in the caption.
+        caption_entities: Vec<MessageEntity>,
         /// The unique identifier of a media message group this message belongs to.
         media_group_id: Option<String>,
     },
@@ -156,6 +172,57 @@ pub enum MessageKind {
         /// Information about the venue.
         data: Venue,
     },
+    /// Message is a dice with a random value.
+    Dice {
+        /// Information about the dice.
+        data: Dice,
+    },
+    /// Message is a game.
+    Game {
+        /// Information about the game.
+        data: Game,
+    },
+    /// Message is an invoice for a payment.
+    Invoice {
+        /// Information about the invoice.
+        data: Invoice,
+    },
+    /// Service message about a successful payment.
+    SuccessfulPayment {
+        /// Information about the payment.
+        data: SuccessfulPayment,
+    },
+    /// Service message: the user logged in on a website with Telegram Login.
+    ConnectedWebsite {
+        /// The domain name of the website.
+        data: String,
+    },
+    /// Service message: a user in the chat triggered another user's proximity alert
+    /// while sharing Live Location.
+    ProximityAlertTriggered {
+        /// Information about the alert.
+        data: ProximityAlertTriggered,
+    },
+    /// Service message: auto-delete timer settings changed in the chat.
+    MessageAutoDeleteTimerChanged {
+        /// Information about the new settings.
+        data: MessageAutoDeleteTimerChanged,
+    },
+    /// Service message: video chat started.
+    VideoChatStarted {
+        /// Information about the video chat.
+        data: VideoChatStarted,
+    },
+    /// Service message: video chat ended.
+    VideoChatEnded {
+        /// Information about the video chat.
+        data: VideoChatEnded,
+    },
+    /// Service message: new participants invited to a video chat.
+    VideoChatParticipantsInvited {
+        /// Information about the invited participants.
+        data: VideoChatParticipantsInvited,
+    },
     /// New members that were added to the group or supergroup and
     /// information about them (the bot itself may be one of these members)
     NewChatMembers {
@@ -212,6 +279,13 @@ pub enum MessageKind {
 }
 
 impl Message {
+    /// Entities of the text or the caption of the message, together with the text they cover.
+    ///
+    /// Entities which don't fit the text are skipped.
+    pub fn entity_texts(&self) -> Vec<(MessageEntity, &str)> {
+        self.kind.entity_texts()
+    }
+
     fn from_raw_message(raw: RawMessage) -> Result<Self, String> {
         let id = raw.message_id;
         let from = match raw.from.clone() {
@@ -287,6 +361,7 @@ impl Message {
                     return make_message(MessageKind::$variant {
                         data: val,
                         caption: raw.caption,
+                        caption_entities: raw.caption_entities.unwrap_or_default(),
                     });
                 }
             }};
@@ -298,6 +373,7 @@ impl Message {
                     return make_message(MessageKind::$variant {
                         data: val,
                         caption: raw.caption,
+                        caption_entities: raw.caption_entities.unwrap_or_default(),
                         media_group_id: raw.media_group_id,
                     });
                 }
@@ -321,6 +397,8 @@ impl Message {
         }
 
         maybe_field!(audio, Audio);
+        // Animations also come with a document for older clients.
+        maybe_field_with_caption!(animation, Animation);
         maybe_field_with_caption!(document, Document);
         maybe_field_with_caption_and_group!(photo, Photo);
         maybe_field!(sticker, Sticker);
@@ -331,6 +409,22 @@ impl Message {
         maybe_field!(location, Location);
         maybe_field!(poll, Poll);
         maybe_field!(venue, Venue);
+        maybe_field!(dice, Dice);
+        maybe_field!(game, Game);
+        maybe_field!(invoice, Invoice);
+        maybe_field!(successful_payment, SuccessfulPayment);
+        maybe_field!(connected_website, ConnectedWebsite);
+        maybe_field!(proximity_alert_triggered, ProximityAlertTriggered);
+        maybe_field!(
+            message_auto_delete_timer_changed,
+            MessageAutoDeleteTimerChanged
+        );
+        maybe_field!(video_chat_started, VideoChatStarted);
+        maybe_field!(video_chat_ended, VideoChatEnded);
+        maybe_field!(
+            video_chat_participants_invited,
+            VideoChatParticipantsInvited
+        );
         maybe_field!(new_chat_members, NewChatMembers);
         maybe_field!(left_chat_member, LeftChatMember);
         maybe_field!(new_chat_title, NewChatTitle);
@@ -348,6 +442,50 @@ impl Message {
     }
 }
 
+impl MessageKind {
+    /// The text or the caption of the message together with its entities.
+    pub fn text_with_entities(&self) -> Option<(&str, &[MessageEntity])> {
+        match self {
+            MessageKind::Text { data, entities } => Some((data, entities)),
+            MessageKind::Animation {
+                caption: Some(caption),
+                caption_entities,
+                ..
+            }
+            | MessageKind::Document {
+                caption: Some(caption),
+                caption_entities,
+                ..
+            }
+            | MessageKind::Photo {
+                caption: Some(caption),
+                caption_entities,
+                ..
+            }
+            | MessageKind::Video {
+                caption: Some(caption),
+                caption_entities,
+                ..
+            } => Some((caption, caption_entities)),
+            _ => None,
+        }
+    }
+
+    /// Entities of the text or the caption, together with the text they cover.
+    ///
+    /// Entities which don't fit the text are skipped.
+    pub fn entity_texts(&self) -> Vec<(MessageEntity, &str)> {
+        let (text, entities) = match self.text_with_entities() {
+            Some(text_with_entities) => text_with_entities,
+            None => return Vec::new(),
+        };
+        entities
+            .iter()
+            .filter_map(|entity| entity.text(text).map(|part| (entity.clone(), part)))
+            .collect()
+    }
+}
+
 impl<'de> Deserialize<'de> for Message {
     fn deserialize<D>(deserializer: D) -> Result<Message, D::Error>
     where
@@ -360,6 +498,13 @@ impl<'de> Deserialize<'de> for Message {
 }
 
 impl ChannelPost {
+    /// Entities of the text or the caption of the post, together with the text they cover.
+    ///
+    /// Entities which don't fit the text are skipped.
+    pub fn entity_texts(&self) -> Vec<(MessageEntity, &str)> {
+        self.kind.entity_texts()
+    }
+
     fn from_raw_message(raw: RawMessage) -> Result<Self, String> {
         let id = raw.message_id;
         let date = raw.date;
@@ -426,6 +571,7 @@ impl ChannelPost {
                     return make_message(MessageKind::$variant {
                         data: val,
                         caption: raw.caption,
+                        caption_entities: raw.caption_entities.unwrap_or_default(),
                     });
                 }
             }};
@@ -437,6 +583,7 @@ impl ChannelPost {
                     return make_message(MessageKind::$variant {
                         data: val,
                         caption: raw.caption,
+                        caption_entities: raw.caption_entities.unwrap_or_default(),
                         media_group_id: raw.media_group_id,
                     });
                 }
@@ -460,6 +607,8 @@ impl ChannelPost {
         }
 
         maybe_field!(audio, Audio);
+        // Animations also come with a document for older clients.
+        maybe_field_with_caption!(animation, Animation);
         maybe_field_with_caption!(document, Document);
         maybe_field_with_caption_and_group!(photo, Photo);
         maybe_field!(sticker, Sticker);
@@ -470,6 +619,22 @@ impl ChannelPost {
         maybe_field!(location, Location);
         maybe_field!(poll, Poll);
         maybe_field!(venue, Venue);
+        maybe_field!(dice, Dice);
+        maybe_field!(game, Game);
+        maybe_field!(invoice, Invoice);
+        maybe_field!(successful_payment, SuccessfulPayment);
+        maybe_field!(connected_website, ConnectedWebsite);
+        maybe_field!(proximity_alert_triggered, ProximityAlertTriggered);
+        maybe_field!(
+            message_auto_delete_timer_changed,
+            MessageAutoDeleteTimerChanged
+        );
+        maybe_field!(video_chat_started, VideoChatStarted);
+        maybe_field!(video_chat_ended, VideoChatEnded);
+        maybe_field!(
+            video_chat_participants_invited,
+            VideoChatParticipantsInvited
+        );
         maybe_field!(new_chat_members, NewChatMembers);
         maybe_field!(left_chat_member, LeftChatMember);
         maybe_field!(new_chat_title, NewChatTitle);
@@ -554,9 +719,12 @@ pub struct RawMessage {
     pub entities: Option<Vec<MessageEntity>>,
     /// Message is an audio file, information about the file.
     pub audio: Option<Audio>,
+    /// Message is an animation, information about the animation.
+    pub animation: Option<Animation>,
     /// Message is a general file, information about the file.
     pub document: Option<Document>,
-    // pub game: Option<Game>,
+    /// Message is a game, information about the game.
+    pub game: Option<Game>,
     /// Message is a photo, available sizes of the photo.
     pub photo: Option<Vec<PhotoSize>>,
     /// Message is a sticker, information about the sticker.
@@ -569,6 +737,9 @@ pub struct RawMessage {
     pub video_note: Option<VideoNote>,
     /// Caption for the document, photo or video, 0-200 characters.
     pub caption: Option<String>,
+    /// For messages with a caption, special entities like usernames, URLs, bot commands, etc.
+    /// that appear in the caption.
+    pub caption_entities: Option<Vec<MessageEntity>>,
     /// Message is a shared contact, information about the contact.
     pub contact: Option<Contact>,
     /// Message is a shared location, information about the location.
@@ -577,6 +748,28 @@ pub struct RawMessage {
     pub poll: Option<Poll>,
     /// Message is a venue, information about the venue.
     pub venue: Option<Venue>,
+    /// Message is a dice with a random value.
+    pub dice: Option<Dice>,
+    /// Message is an invoice for a payment, information about the invoice.
+    pub invoice: Option<Invoice>,
+    /// Message is a service message about a successful payment, information about the payment.
+    pub successful_payment: Option<SuccessfulPayment>,
+    /// The domain name of the website on which the user has logged in.
+    pub connected_website: Option<String>,
+    /// Service message. A user in the chat triggered another user's proximity alert
+    /// while sharing Live Location.
+    pub proximity_alert_triggered: Option<ProximityAlertTriggered>,
+    /// Service message: auto-delete timer settings changed in the chat.
+    pub message_auto_delete_timer_changed: Option<MessageAutoDeleteTimerChanged>,
+    /// Service message: video chat started.
+    #[serde(alias = "voice_chat_started")]
+    pub video_chat_started: Option<VideoChatStarted>,
+    /// Service message: video chat ended.
+    #[serde(alias = "voice_chat_ended")]
+    pub video_chat_ended: Option<VideoChatEnded>,
+    /// Service message: new participants invited to a video chat.
+    #[serde(alias = "voice_chat_participants_invited")]
+    pub video_chat_participants_invited: Option<VideoChatParticipantsInvited>,
     /// New members that were added to the group or supergroup and information
     /// about them (the bot itself may be one of these members)
     pub new_chat_members: Option<Vec<User>>,
@@ -624,22 +817,67 @@ pub struct MessageEntity {
     pub kind: MessageEntityKind,
 }
 
+impl MessageEntity {
+    /// The part of `text` covered by the entity.
+    ///
+    /// Telegram measures `offset` and `length` in UTF-16 code units, they are converted to
+    /// byte positions in `text`. Returns `None` if the entity doesn't fit the text.
+    pub fn text<'a>(&self, text: &'a str) -> Option<&'a str> {
+        if self.length < 0 {
+            return None;
+        }
+        let start = utf16_byte_position(text, self.offset)?;
+        let end = utf16_byte_position(text, self.offset.checked_add(self.length)?)?;
+        text.get(start..end)
+    }
+}
+
+/// Byte position in `text` of the position `offset` in UTF-16 code units,
+/// `None` if it's out of bounds or inside of a surrogate pair.
+fn utf16_byte_position(text: &str, offset: Integer) -> Option<usize> {
+    let mut position = 0;
+    for (index, c) in text.char_indices() {
+        if position >= offset {
+            return if position == offset {
+                Some(index)
+            } else {
+                None
+            };
+        }
+        position += c.len_utf16() as Integer;
+    }
+    if position == offset {
+        Some(text.len())
+    } else {
+        None
+    }
+}
+
 /// Kind of the entity.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum MessageEntityKind {
     Mention,
     Hashtag,
+    Cashtag,
     BotCommand,
     Url,
     Email,
+    PhoneNumber,
     Bold,
     Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+    Blockquote,
     Code,
-    Pre,
+    /// Monowidth block with the programming language of the code, if specified.
+    Pre(Option<String>),
     TextLink(String), // TODO(knsd) URL?
     TextMention(User),
-    #[doc(hidden)]
-    Unknown(RawMessageEntity),
+    /// Custom emoji with the identifier of the emoji.
+    CustomEmoji(String),
+    /// An entity of a type which this crate doesn't know yet, as received.
+    Unknown(RawJson),
 }
 
 impl<'de> Deserialize<'de> for MessageEntity {
@@ -649,7 +887,9 @@ impl<'de> Deserialize<'de> for MessageEntity {
     {
         use self::MessageEntityKind::*;
 
-        let raw: RawMessageEntity = Deserialize::deserialize(deserializer)?;
+        let json = serde_json::Value::deserialize(deserializer)?;
+        let raw: RawMessageEntity =
+            serde_json::from_value(json.clone()).map_err(D::Error::custom)?;
 
         let offset = raw.offset;
         let length = raw.length;
@@ -666,16 +906,23 @@ impl<'de> Deserialize<'de> for MessageEntity {
         let kind = match raw.type_.as_str() {
             "mention" => Mention,
             "hashtag" => Hashtag,
+            "cashtag" => Cashtag,
             "bot_command" => BotCommand,
             "url" => Url,
             "email" => Email,
+            "phone_number" => PhoneNumber,
             "bold" => Bold,
             "italic" => Italic,
+            "underline" => Underline,
+            "strikethrough" => Strikethrough,
+            "spoiler" => Spoiler,
+            "blockquote" => Blockquote,
             "code" => Code,
-            "pre" => Pre,
+            "pre" => Pre(raw.language),
             "text_link" => TextLink(required_field!(url)),
             "text_mention" => TextMention(required_field!(user)),
-            _ => Unknown(raw),
+            "custom_emoji" => CustomEmoji(required_field!(custom_emoji_id)),
+            _ => Unknown(RawJson(json)),
         };
 
         Ok(MessageEntity {
@@ -686,13 +933,77 @@ impl<'de> Deserialize<'de> for MessageEntity {
     }
 }
 
+impl Serialize for MessageEntity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use self::MessageEntityKind::*;
+
+        let mut raw = RawMessageEntity {
+            type_: String::new(),
+            offset: self.offset,
+            length: self.length,
+            url: None,
+            user: None,
+            language: None,
+            custom_emoji_id: None,
+        };
+
+        raw.type_ = match self.kind {
+            Mention => "mention",
+            Hashtag => "hashtag",
+            Cashtag => "cashtag",
+            BotCommand => "bot_command",
+            Url => "url",
+            Email => "email",
+            PhoneNumber => "phone_number",
+            Bold => "bold",
+            Italic => "italic",
+            Underline => "underline",
+            Strikethrough => "strikethrough",
+            Spoiler => "spoiler",
+            Blockquote => "blockquote",
+            Code => "code",
+            Pre(ref language) => {
+                raw.language = language.clone();
+                "pre"
+            }
+            TextLink(ref url) => {
+                raw.url = Some(url.clone());
+                "text_link"
+            }
+            TextMention(ref user) => {
+                raw.user = Some(user.clone());
+                "text_mention"
+            }
+            CustomEmoji(ref id) => {
+                raw.custom_emoji_id = Some(id.clone());
+                "custom_emoji"
+            }
+            Unknown(ref json) => {
+                let mut json = json.0.clone();
+                if let Some(object) = json.as_object_mut() {
+                    object.insert("offset".into(), self.offset.into());
+                    object.insert("length".into(), self.length.into());
+                }
+                return json.serialize(serializer);
+            }
+        }
+        .to_string();
+
+        raw.serialize(serializer)
+    }
+}
+
 /// This object represents one special entity in a text message.
 /// For example, hashtags, usernames, URLs, etc. Directly mapped.
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct RawMessageEntity {
-    /// Type of the entity. Can be mention (@username), hashtag, bot_command, url, email,
-    /// bold (bold text), italic (italic text), code (monowidth string), pre (monowidth block),
-    /// text_link (for clickable text URLs), text_mention (for users without usernames).
+    /// Type of the entity. Can be mention (@username), hashtag, cashtag, bot_command, url, email,
+    /// phone_number, bold (bold text), italic (italic text), underline, strikethrough, spoiler,
+    /// blockquote, code (monowidth string), pre (monowidth block), text_link (for clickable
+    /// text URLs), text_mention (for users without usernames), custom_emoji.
     #[serde(rename = "type")]
     pub type_: String,
     /// Offset in UTF-16 code units to the start of the entity.
@@ -700,9 +1011,17 @@ pub struct RawMessageEntity {
     /// Length of the entity in UTF-16 code units.
     pub length: Integer,
     /// For “text_link” only, url that will be opened after user taps on the text.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
     /// For “text_mention” only, the mentioned user.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<User>,
+    /// For “pre” only, the programming language of the entity text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// For “custom_emoji” only, unique identifier of the custom emoji.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_emoji_id: Option<String>,
 }
 
 /// This object represents one size of a photo or a file / sticker thumbnail.
@@ -735,6 +1054,30 @@ pub struct Audio {
     pub file_size: Option<Integer>,
 }
 
+/// This object represents an animation file (GIF or H.264/MPEG-4 AVC video without sound).
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct Animation {
+    /// Identifier for this file, which can be used to download or reuse the file.
+    pub file_id: String,
+    /// Unique identifier for this file, which is supposed to be the same over time and for different bots.
+    /// Can't be used to download or reuse the file.
+    pub file_unique_id: String,
+    /// Video width as defined by sender.
+    pub width: Integer,
+    /// Video height as defined by sender.
+    pub height: Integer,
+    /// Duration of the video in seconds as defined by sender.
+    pub duration: Integer,
+    /// Animation thumbnail as defined by sender.
+    pub thumb: Option<PhotoSize>,
+    /// Original animation filename as defined by sender.
+    pub file_name: Option<String>,
+    /// MIME type of the file as defined by sender.
+    pub mime_type: Option<String>,
+    /// File size.
+    pub file_size: Option<Integer>,
+}
+
 /// This object represents a general file (as opposed to photos, voice messages and audio files).
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
 pub struct Document {
@@ -853,6 +1196,71 @@ pub struct Venue {
     pub foursquare_id: Option<String>,
 }
 
+/// This object represents an animated emoji that displays a random value.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct Dice {
+    /// Emoji on which the dice throw animation is based.
+    pub emoji: String,
+    /// Value of the dice, 1-6 for “🎲”, “🎯” and “🎳” base emoji,
+    /// 1-5 for “🏀” and “⚽” base emoji, 1-64 for “🎰” base emoji.
+    pub value: Integer,
+}
+
+/// This object represents a game.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct Game {
+    /// Title of the game.
+    pub title: String,
+    /// Description of the game.
+    pub description: String,
+    /// Photo that will be displayed in the game message in chats.
+    pub photo: Vec<PhotoSize>,
+    /// Brief description of the game or high scores included in the game message, 0-4096 characters.
+    pub text: Option<String>,
+    /// Special entities that appear in text, such as usernames, URLs, bot commands, etc.
+    pub text_entities: Option<Vec<MessageEntity>>,
+    /// Animation that will be displayed in the game message in chats.
+    pub animation: Option<Animation>,
+}
+
+/// This object represents the content of a service message, sent whenever a user in the chat
+/// triggers a proximity alert set by another user.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct ProximityAlertTriggered {
+    /// User that triggered the alert.
+    pub traveler: User,
+    /// User that set the alert.
+    pub watcher: User,
+    /// The distance between the users.
+    pub distance: Integer,
+}
+
+/// This object represents a service message about a change in auto-delete timer settings.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct MessageAutoDeleteTimerChanged {
+    /// New auto-delete time for messages in the chat, in seconds.
+    pub message_auto_delete_time: Integer,
+}
+
+/// This object represents a service message about a video chat started in the chat.
+/// Currently holds no information.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct VideoChatStarted {}
+
+/// This object represents a service message about a video chat ended in the chat.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct VideoChatEnded {
+    /// Video chat duration in seconds.
+    pub duration: Integer,
+}
+
+/// This object represents a service message about new members invited to a video chat.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct VideoChatParticipantsInvited {
+    /// New members that were invited to the video chat.
+    pub users: Option<Vec<User>>,
+}
+
 /// This object contains information about a poll.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
 pub struct Poll {
@@ -938,10 +1346,16 @@ pub struct File {
 }
 
 impl File {
+    /// Download URL of the file on the Bot API server from the `TELEGRAM_API_URL` environment variable.
     pub fn get_url(&self, token: &str) -> Option<String> {
+        self.get_url_with_base(&telegram_api_url(), token)
+    }
+
+    /// Download URL of the file on the Bot API server at `api_url`, e.g. `https://api.telegram.org/`.
+    pub fn get_url_with_base(&self, api_url: &str, token: &str) -> Option<String> {
         self.file_path
             .as_ref()
-            .map(|path| format!("{}file/bot{}/{}", telegram_api_url(), token, path))
+            .map(|path| format!("{}file/bot{}/{}", api_url, token, path))
     }
 }
 
@@ -7,12 +7,14 @@ pub mod inline_query_result;
 pub mod input_file;
 pub mod message;
 pub mod my_chat_member;
+pub mod payments;
 pub mod primitive;
 pub mod refs;
 pub mod reply_markup;
 pub mod response_parameters;
 pub mod text;
 pub mod update;
+pub mod webhook_info;
 
 pub use self::callback_query::*;
 pub use self::chat::*;
@@ -23,9 +25,11 @@ pub use self::inline_query_result::*;
 pub use self::input_file::*;
 pub use self::message::*;
 pub use self::my_chat_member::*;
+pub use self::payments::*;
 pub use self::primitive::*;
 pub use self::refs::*;
 pub use self::reply_markup::*;
 pub use self::response_parameters::*;
 pub use self::text::*;
 pub use self::update::*;
+pub use self::webhook_info::*;
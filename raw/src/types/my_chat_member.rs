@@ -1,11 +1,21 @@
 use crate::types::*;
 use serde::Deserialize;
 
+/// Changes in the status of a chat member.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
 pub struct MyChatMember {
-    chat: Chat,
-    from: User,
-    date: Integer,
-    old_chat_member: ChatMember,
-    new_chat_member: ChatMember,
+    /// Chat the user belongs to.
+    pub chat: Chat,
+    /// Performer of the action, which resulted in the change.
+    pub from: User,
+    /// Date the change was done in Unix time.
+    pub date: Integer,
+    /// Previous information about the chat member.
+    pub old_chat_member: ChatMember,
+    /// New information about the chat member.
+    pub new_chat_member: ChatMember,
 }
+
+/// Changes in the status of a chat member, as received in
+/// [`UpdateKind::ChatMember`](enum.UpdateKind.html#variant.ChatMember).
+pub type ChatMemberUpdated = MyChatMember;
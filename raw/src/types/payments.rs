@@ -0,0 +1,66 @@
+use crate::types::*;
+
+/// This object contains basic information about an invoice.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct Invoice {
+    /// Product name.
+    pub title: String,
+    /// Product description.
+    pub description: String,
+    /// Unique bot deep-linking parameter that can be used to generate this invoice.
+    pub start_parameter: String,
+    /// Three-letter ISO 4217 currency code.
+    pub currency: String,
+    /// Total price in the smallest units of the currency (integer, not float/double).
+    /// For example, for a price of US$ 1.45 `total_amount = 145`.
+    pub total_amount: Integer,
+}
+
+/// This object contains basic information about a successful payment.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct SuccessfulPayment {
+    /// Three-letter ISO 4217 currency code.
+    pub currency: String,
+    /// Total price in the smallest units of the currency (integer, not float/double).
+    pub total_amount: Integer,
+    /// Bot specified invoice payload.
+    pub invoice_payload: String,
+    /// Identifier of the shipping option chosen by the user.
+    pub shipping_option_id: Option<String>,
+    /// Order info provided by the user.
+    pub order_info: Option<OrderInfo>,
+    /// Telegram payment identifier.
+    pub telegram_payment_charge_id: String,
+    /// Provider payment identifier.
+    pub provider_payment_charge_id: String,
+}
+
+/// This object represents information about an order.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct OrderInfo {
+    /// User name.
+    pub name: Option<String>,
+    /// User's phone number.
+    pub phone_number: Option<String>,
+    /// User email.
+    pub email: Option<String>,
+    /// User shipping address.
+    pub shipping_address: Option<ShippingAddress>,
+}
+
+/// This object represents a shipping address.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+pub struct ShippingAddress {
+    /// ISO 3166-1 alpha-2 country code.
+    pub country_code: String,
+    /// State, if applicable.
+    pub state: String,
+    /// City.
+    pub city: String,
+    /// First line for the address.
+    pub street_line1: String,
+    /// Second line for the address.
+    pub street_line2: String,
+    /// Address post code.
+    pub post_code: String,
+}
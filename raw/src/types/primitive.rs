@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
 
 use serde::de;
 use serde::de::{Deserialize, Deserializer, Unexpected, Visitor};
@@ -50,3 +53,72 @@ impl Serialize for True {
         serializer.serialize_bool(true)
     }
 }
+
+/// JSON value as received from Telegram, kept for objects which this crate doesn't know.
+///
+/// Dereferences to a `serde_json::Value`. Values are ordered and hashed by their JSON text.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawJson(pub serde_json::Value);
+
+impl RawJson {
+    /// Consume the `RawJson`, returning the JSON value.
+    pub fn into_inner(self) -> serde_json::Value {
+        self.0
+    }
+}
+
+impl Deref for RawJson {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &serde_json::Value {
+        &self.0
+    }
+}
+
+impl From<serde_json::Value> for RawJson {
+    fn from(value: serde_json::Value) -> Self {
+        RawJson(value)
+    }
+}
+
+impl fmt::Display for RawJson {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl PartialOrd for RawJson {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RawJson {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.to_string().cmp(&other.0.to_string())
+    }
+}
+
+impl Hash for RawJson {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_string().hash(state)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawJson {
+    fn deserialize<D>(deserializer: D) -> Result<RawJson, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_json::Value::deserialize(deserializer).map(RawJson)
+    }
+}
+
+impl Serialize for RawJson {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
@@ -12,6 +12,8 @@ pub enum ResponseWrapper<T> {
     },
     /// Request was unsuccessful.
     Error {
+        /// Error code, its meaning is subject to change.
+        error_code: Option<Integer>,
         /// Human-readable description of the result.
         description: String,
         /// Contains information about why a request was unsuccessful.
@@ -27,6 +29,7 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for ResponseWrapper<T> {
         let raw: RawResponse<T> = Deserialize::deserialize(deserializer)?;
         match (raw.ok, raw.description, raw.result) {
             (false, Some(description), None) => Ok(ResponseWrapper::Error {
+                error_code: raw.error_code,
                 description: description,
                 parameters: raw.parameters,
             }),
@@ -41,6 +44,8 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for ResponseWrapper<T> {
 pub struct RawResponse<T> {
     /// If ‘ok’ equals true, the request was successful.
     ok: bool,
+    /// Error code of an unsuccessful request.
+    error_code: Option<Integer>,
     /// Human-readable description of the result.
     description: Option<String>,
     /// Result of the query.
@@ -1,17 +1,50 @@
+use serde::de::{self, Deserialize, Deserializer, Error, Visitor};
+
 use crate::types::*;
 
 /// This object represents an incoming update.
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Update {
     /// The update‘s unique identifier. Update identifiers start from a certain
     /// positive number and increase sequentially.
-    #[serde(rename = "update_id")]
     pub id: Integer,
     /// Kind of the incoming update.
-    #[serde(flatten)]
     pub kind: UpdateKind,
 }
 
+impl<'de> Deserialize<'de> for Update {
+    fn deserialize<D>(deserializer: D) -> Result<Update, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let id = match raw.get("update_id").and_then(serde_json::Value::as_i64) {
+            Some(id) => id,
+            None => return Err(D::Error::missing_field("update_id")),
+        };
+
+        // Decode the kind separately, so that a single broken update doesn't fail a whole batch.
+        let mut fields = raw.as_object().cloned().unwrap_or_default();
+        fields.remove("update_id");
+        let kinds = update_kinds();
+        if !fields.keys().any(|key| kinds.contains(&key.as_str())) {
+            return Ok(Update {
+                id,
+                kind: UpdateKind::Unknown(RawJson(raw)),
+            });
+        }
+        let kind = match serde_json::from_value(serde_json::Value::Object(fields)) {
+            Ok(kind) => kind,
+            Err(error) => UpdateKind::Undecodable(UndecodableUpdate {
+                error: error.to_string(),
+                raw: RawJson(raw),
+            }),
+        };
+
+        Ok(Update { id, kind })
+    }
+}
+
 /// Kind of the incoming update.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize)]
 pub enum UpdateKind {
@@ -39,10 +72,63 @@ pub enum UpdateKind {
     /// A user changed their answer in a non-anonymous poll. Bots receive new votes only in polls that were sent by the bot itself
     #[serde(rename = "poll_answer")]
     PollAnswer(PollAnswer),
+    /// The bot's chat member status was updated in a chat.
     #[serde(rename = "my_chat_member")]
     MyChatMember(MyChatMember),
-    #[doc(hidden)]
-    Error(String),
-    #[doc(hidden)]
-    Unknown,
+    /// A chat member's status was updated in a chat, the bot must be an administrator
+    /// in the chat and must request this kind explicitly in `allowed_updates`.
+    #[serde(rename = "chat_member")]
+    ChatMember(ChatMemberUpdated),
+    /// The update could not be decoded, e.g. because it lacks a required field.
+    #[serde(skip)]
+    Undecodable(UndecodableUpdate),
+    /// An update of a kind which this crate doesn't know yet, e.g. one added by a newer
+    /// Bot API version. Holds the whole update as received.
+    #[serde(skip)]
+    Unknown(RawJson),
+}
+
+/// Names of the update kinds known to this crate, as listed by the derived `Deserialize`
+/// of [`UpdateKind`](enum.UpdateKind.html). Other kinds are decoded as `UpdateKind::Unknown`.
+fn update_kinds() -> &'static [&'static str] {
+    /// Deserializer which only records the variant names of the enum asking for it.
+    struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de, 'a> Deserializer<'de> for VariantNames<'a> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(Self::Error::custom("expected an enum"))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = variants;
+            Err(Self::Error::custom("variant names recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct identifier ignored_any
+        }
+    }
+
+    let mut kinds: &'static [&'static str] = &[];
+    let _ = UpdateKind::deserialize(VariantNames(&mut kinds));
+    kinds
+}
+
+/// An update which could not be decoded, see
+/// [`UpdateKind::Undecodable`](enum.UpdateKind.html#variant.Undecodable).
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct UndecodableUpdate {
+    /// Reason why decoding failed.
+    pub error: String,
+    /// The update as received from Telegram.
+    pub raw: RawJson,
 }
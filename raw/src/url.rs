@@ -4,6 +4,8 @@ const TELEGRAM_API_URL_DEFAULT: &str = "https://api.telegram.org/";
 
 /// Obtains URL to the Telegram Bot API. You're able to change this URL to point to fake Telegram server
 /// for E2E-testing by setting `TELEGRAM_API_URL` environment variable.
+///
+/// This is only the default, `telegram_bot::ApiBuilder::api_url` sets the URL for a single `Api`.
 pub fn telegram_api_url() -> String {
     match env::var("TELEGRAM_API_URL") {
         Ok(url) => url,
@@ -1,7 +1,10 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-use telegram_bot_raw::types::message::MessageKind;
+use telegram_bot_raw::types::chat::MessageChat;
+use telegram_bot_raw::types::chat_member::ChatMemberStatus;
+use telegram_bot_raw::types::message::{MessageEntityKind, MessageKind};
+use telegram_bot_raw::types::refs::{ChatId, UserId};
 use telegram_bot_raw::types::update::{Update, UpdateKind};
 
 macro_rules! make_test {
@@ -54,3 +57,225 @@ make_test!(regression_test_208, |update: Update| {
 
     assert!(false)
 });
+
+make_test!(undecodable_update, |update: Update| {
+    assert_eq!(update.id, 424151281);
+    match update.kind {
+        UpdateKind::Undecodable(undecodable) => {
+            assert!(undecodable.error.contains("from"));
+            assert_eq!(undecodable.raw["message"]["message_id"], 3);
+        }
+        kind => panic!("unexpected update kind: {:?}", kind),
+    }
+});
+
+make_test!(unknown_update, |update: Update| {
+    match update.kind {
+        UpdateKind::Unknown(raw) => {
+            assert_eq!(raw["update_id"], 424151282);
+            assert_eq!(raw["chat_join_request"]["from"]["id"], 174976101);
+        }
+        kind => panic!("unexpected update kind: {:?}", kind),
+    }
+});
+
+#[test]
+fn known_kinds_are_decoded() {
+    for kind in &[
+        "message",
+        "edited_message",
+        "channel_post",
+        "edited_channel_post",
+        "inline_query",
+        "chosen_inline_result",
+        "callback_query",
+        "poll",
+        "poll_answer",
+        "my_chat_member",
+        "chat_member",
+    ] {
+        let update = serde_json::json!({ "update_id": 1, *kind: {} });
+        match serde_json::from_value::<Update>(update).unwrap().kind {
+            UpdateKind::Undecodable(_) => (),
+            kind => panic!("unexpected update kind: {:?}", kind),
+        }
+    }
+}
+
+make_test!(chat_member, |update: Update| match update.kind {
+    UpdateKind::ChatMember(member) => {
+        assert_eq!(member.from.id, UserId::new(174976101));
+        assert_eq!(member.old_chat_member.status, ChatMemberStatus::Left);
+        assert_eq!(member.new_chat_member.status, ChatMemberStatus::Member);
+    }
+    kind => panic!("unexpected update kind: {:?}", kind),
+});
+
+make_test!(unknown_chat_and_entity, |update: Update| {
+    let message = match update.kind {
+        UpdateKind::Message(message) => message,
+        kind => panic!("unexpected update kind: {:?}", kind),
+    };
+    match message.chat {
+        MessageChat::Unknown(ref raw) => assert_eq!(raw["type"], "future_chat"),
+        ref chat => panic!("unexpected chat: {:?}", chat),
+    }
+    assert_eq!(message.chat.id(), ChatId::new(-42));
+    match message.kind {
+        MessageKind::Text { entities, .. } => match entities[0].kind {
+            MessageEntityKind::Unknown(ref raw) => assert_eq!(raw["future_field"], 1),
+            ref kind => panic!("unexpected entity kind: {:?}", kind),
+        },
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+});
+
+make_test!(entities, |update: Update| {
+    let message = match update.kind {
+        UpdateKind::Message(message) => message,
+        kind => panic!("unexpected update kind: {:?}", kind),
+    };
+    let entities: Vec<_> = message
+        .entity_texts()
+        .into_iter()
+        .map(|(entity, text)| (entity.kind, text))
+        .collect();
+    // Entities splitting the emoji or reaching past the text are skipped.
+    assert_eq!(
+        entities,
+        vec![
+            (MessageEntityKind::Pre(Some("rust".to_string())), "Hi"),
+            (
+                MessageEntityKind::CustomEmoji("5368324170671202286".to_string()),
+                "👋"
+            ),
+            (MessageEntityKind::Bold, "bold"),
+            (MessageEntityKind::Spoiler, "spoiler"),
+            (MessageEntityKind::Cashtag, "$TON"),
+        ]
+    );
+});
+
+fn message_kind(update: Update) -> MessageKind {
+    match update.kind {
+        UpdateKind::Message(message) => message.kind,
+        kind => panic!("unexpected update kind: {:?}", kind),
+    }
+}
+
+make_test!(animation, |update: Update| {
+    let kind = message_kind(update);
+    let entities: Vec<_> = kind
+        .entity_texts()
+        .into_iter()
+        .map(|(entity, text)| (entity.kind, text))
+        .collect();
+    assert_eq!(entities, vec![(MessageEntityKind::Italic, "cat")]);
+    match kind {
+        MessageKind::Animation { data, caption, .. } => {
+            assert_eq!(data.duration, 3);
+            assert_eq!(data.file_name.as_deref(), Some("cat.mp4"));
+            assert_eq!(caption.as_deref(), Some("funny cat"));
+        }
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+});
+
+make_test!(dice, |update: Update| match message_kind(update) {
+    MessageKind::Dice { data } => {
+        assert_eq!(data.emoji, "🎲");
+        assert_eq!(data.value, 4);
+    }
+    kind => panic!("unexpected message kind: {:?}", kind),
+});
+
+make_test!(game, |update: Update| match message_kind(update) {
+    MessageKind::Game { data } => {
+        assert_eq!(data.title, "Lumberjack");
+        assert_eq!(data.photo.len(), 1);
+        assert_eq!(data.text_entities.unwrap().len(), 1);
+        assert!(data.animation.is_some());
+    }
+    kind => panic!("unexpected message kind: {:?}", kind),
+});
+
+make_test!(invoice, |update: Update| match message_kind(update) {
+    MessageKind::Invoice { data } => {
+        assert_eq!(data.currency, "USD");
+        assert_eq!(data.total_amount, 145);
+    }
+    kind => panic!("unexpected message kind: {:?}", kind),
+});
+
+make_test!(
+    successful_payment,
+    |update: Update| match message_kind(update) {
+        MessageKind::SuccessfulPayment { data } => {
+            assert_eq!(data.invoice_payload, "order-1");
+            let address = data.order_info.unwrap().shipping_address.unwrap();
+            assert_eq!(address.city, "Moscow");
+        }
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+);
+
+make_test!(
+    connected_website,
+    |update: Update| match message_kind(update) {
+        MessageKind::ConnectedWebsite { data } => assert_eq!(data, "example.com"),
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+);
+
+make_test!(proximity_alert_triggered, |update: Update| {
+    match message_kind(update) {
+        MessageKind::ProximityAlertTriggered { data } => {
+            assert_eq!(data.traveler.first_name, "Fedor");
+            assert_eq!(data.watcher.first_name, "Ivan");
+            assert_eq!(data.distance, 42);
+        }
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+});
+
+make_test!(message_auto_delete_timer_changed, |update: Update| {
+    match message_kind(update) {
+        MessageKind::MessageAutoDeleteTimerChanged { data } => {
+            assert_eq!(data.message_auto_delete_time, 86400)
+        }
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+});
+
+make_test!(
+    video_chat_started,
+    |update: Update| match message_kind(update) {
+        MessageKind::VideoChatStarted { .. } => (),
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+);
+
+make_test!(
+    voice_chat_started,
+    |update: Update| match message_kind(update) {
+        MessageKind::VideoChatStarted { .. } => (),
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+);
+
+make_test!(
+    video_chat_ended,
+    |update: Update| match message_kind(update) {
+        MessageKind::VideoChatEnded { data } => assert_eq!(data.duration, 3600),
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+);
+
+make_test!(video_chat_participants_invited, |update: Update| {
+    match message_kind(update) {
+        MessageKind::VideoChatParticipantsInvited { data } => {
+            assert_eq!(data.users.unwrap()[0].first_name, "Ivan")
+        }
+        kind => panic!("unexpected message kind: {:?}", kind),
+    }
+});
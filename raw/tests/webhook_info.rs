@@ -0,0 +1,15 @@
+use telegram_bot_raw::types::webhook_info::WebhookInfo;
+use telegram_bot_raw::AllowedUpdate;
+
+#[test]
+fn unknown_update_type() {
+    let info: WebhookInfo = serde_json::from_str(
+        r#"{"url":"https://example.com","has_custom_certificate":false,
+            "pending_update_count":0,"allowed_updates":["message","message_reaction"]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        info.allowed_updates,
+        Some(vec![AllowedUpdate::Message, AllowedUpdate::Unknown])
+    );
+}